@@ -0,0 +1,206 @@
+//! Calendar arithmetic shared by the backend and the UI, so both agree on
+//! which day a todo is due and where moving it lands, in any time zone and
+//! across daylight saving changes.
+//!
+//! Instants are always UTC; days and times of day are local to the time
+//! zone passed in, usually `chrono::Local`.
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc, Weekday,
+};
+
+/// Daylight saving gaps are shorter than this many minutes.
+const MAX_GAP_MINUTES: i64 = 3 * 60;
+const GAP_STEP_MINUTES: i64 = 15;
+
+/// The day `instant` falls on in `tz`.
+pub fn day_of<Tz: TimeZone>(instant: DateTime<Utc>, tz: &Tz) -> NaiveDate {
+    instant.with_timezone(tz).date_naive()
+}
+
+/// The instant a local time in `tz` stands for. A time repeated when the
+/// clocks go back is the first of the two; a time skipped when they go
+/// forward is moved forward past the gap.
+pub fn resolve<Tz: TimeZone>(local: NaiveDateTime, tz: &Tz) -> Option<DateTime<Utc>> {
+    let instant = match tz.from_local_datetime(&local) {
+        LocalResult::Single(instant) | LocalResult::Ambiguous(instant, _) => instant,
+        LocalResult::None => {
+            let instant = (1..=MAX_GAP_MINUTES / GAP_STEP_MINUTES).find_map(|step| {
+                let later = local.checked_add_signed(Duration::minutes(step * GAP_STEP_MINUTES))?;
+                tz.from_local_datetime(&later).earliest()
+            });
+            instant?
+        }
+    };
+    Some(instant.with_timezone(&Utc))
+}
+
+/// When `day` starts in `tz`: midnight, unless a daylight saving change
+/// skips it.
+pub fn start_of_day<Tz: TimeZone>(day: NaiveDate, tz: &Tz) -> Option<DateTime<Utc>> {
+    resolve(day.and_time(NaiveTime::MIN), tz)
+}
+
+/// `instant` moved by `days` days in `tz`, keeping its local time of day, so
+/// something at 9:00 stays at 9:00 even when a day is 23 or 25 hours long.
+pub fn add_days<Tz: TimeZone>(
+    instant: DateTime<Utc>,
+    days: i64,
+    tz: &Tz,
+) -> Option<DateTime<Utc>> {
+    let local = instant.with_timezone(tz).naive_local();
+    let moved = if days >= 0 {
+        local.checked_add_days(Days::new(days.unsigned_abs()))
+    } else {
+        local.checked_sub_days(Days::new(days.unsigned_abs()))
+    }?;
+    resolve(moved, tz)
+}
+
+/// `instant` moved to `day` in `tz`, keeping its local time of day.
+pub fn move_to_day<Tz: TimeZone>(
+    instant: DateTime<Utc>,
+    day: NaiveDate,
+    tz: &Tz,
+) -> Option<DateTime<Utc>> {
+    resolve(day.and_time(instant.with_timezone(tz).time()), tz)
+}
+
+/// The first day of the week holding `day`, for weeks starting on `first`.
+pub fn week_start(day: NaiveDate, first: Weekday) -> NaiveDate {
+    day.week(first).first_day()
+}
+
+/// The seven days of the week holding `day`.
+pub fn week(day: NaiveDate, first: Weekday) -> Vec<NaiveDate> {
+    week_start(day, first).iter_days().take(7).collect()
+}
+
+/// The days a month view shows for the month holding `day`: whole weeks,
+/// from the one holding the 1st to the one holding the last day.
+pub fn month_grid(day: NaiveDate, first: Weekday) -> Vec<NaiveDate> {
+    let first_of_month = day.with_day(1).unwrap_or(day);
+    let last_of_month = first_of_month
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(day);
+    let start = week_start(first_of_month, first);
+    let end = week_start(last_of_month, first) + Duration::days(6);
+    start.iter_days().take_while(|day| *day <= end).collect()
+}
+
+/// Whether `a` and `b` are in the same month of the same year.
+pub fn same_month(a: NaiveDate, b: NaiveDate) -> bool {
+    a.year() == b.year() && a.month() == b.month()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+    use chrono_tz::America::Sao_Paulo;
+    use chrono_tz::Europe::Berlin;
+
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn local(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn resolve_with_a_fixed_offset() {
+        let tz = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        assert_eq!(resolve(local("2024-01-01 09:00"), &tz), Some(utc("2024-01-01T03:30:00Z")));
+    }
+
+    #[test]
+    fn resolve_moves_a_skipped_time_past_the_gap() {
+        // clocks go from 2:00 to 3:00 CEST
+        assert_eq!(
+            resolve(local("2024-03-31 02:30"), &Berlin),
+            Some(utc("2024-03-31T01:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn resolve_takes_the_first_of_a_repeated_time() {
+        // clocks go from 3:00 CEST back to 2:00 CET
+        assert_eq!(
+            resolve(local("2024-10-27 02:30"), &Berlin),
+            Some(utc("2024-10-27T00:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn start_of_a_day_whose_midnight_is_skipped() {
+        // clocks went from 0:00 to 1:00
+        assert_eq!(start_of_day(date(2018, 11, 4), &Sao_Paulo), Some(utc("2018-11-04T03:00:00Z")));
+        assert_eq!(start_of_day(date(2018, 11, 5), &Sao_Paulo), Some(utc("2018-11-05T02:00:00Z")));
+    }
+
+    #[test]
+    fn add_days_keeps_the_local_time_across_daylight_saving() {
+        // a 23 hour day
+        let before = utc("2024-03-30T08:00:00Z");
+        assert_eq!(add_days(before, 1, &Berlin), Some(utc("2024-03-31T07:00:00Z")));
+        assert_eq!(add_days(utc("2024-03-31T07:00:00Z"), -1, &Berlin), Some(before));
+        // a 25 hour day
+        assert_eq!(
+            add_days(utc("2024-10-26T07:00:00Z"), 1, &Berlin),
+            Some(utc("2024-10-27T08:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn add_days_onto_a_skipped_time() {
+        assert_eq!(
+            add_days(utc("2024-03-30T01:30:00Z"), 1, &Berlin),
+            Some(utc("2024-03-31T01:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn move_to_day_keeps_the_local_time() {
+        // 9:15 CET to 9:15 CEST
+        assert_eq!(
+            move_to_day(utc("2024-03-20T08:15:00Z"), date(2024, 4, 2), &Berlin),
+            Some(utc("2024-04-02T07:15:00Z"))
+        );
+        // 2:30 doesn't exist on the day the clocks go forward
+        assert_eq!(
+            move_to_day(utc("2024-03-20T01:30:00Z"), date(2024, 3, 31), &Berlin),
+            Some(utc("2024-03-31T01:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn month_grid_is_whole_weeks_from_the_first_day() {
+        // March 2024 runs from a Friday to a Sunday
+        let monday = month_grid(date(2024, 3, 15), Weekday::Mon);
+        assert_eq!(monday.len(), 35);
+        assert_eq!(monday.first(), Some(&date(2024, 2, 26)));
+        assert_eq!(monday.last(), Some(&date(2024, 3, 31)));
+
+        let sunday = month_grid(date(2024, 3, 15), Weekday::Sun);
+        assert_eq!(sunday.len(), 42);
+        assert_eq!(sunday.first(), Some(&date(2024, 2, 25)));
+        assert_eq!(sunday.last(), Some(&date(2024, 4, 6)));
+    }
+
+    #[test]
+    fn month_grid_of_a_month_that_fills_whole_weeks() {
+        // February 2021 starts on a Monday and has four weeks
+        let grid = month_grid(date(2021, 2, 10), Weekday::Mon);
+        assert_eq!(grid.first(), Some(&date(2021, 2, 1)));
+        assert_eq!(grid.len(), 28);
+        assert_eq!(month_grid(date(2021, 2, 10), Weekday::Sun).len(), 35);
+    }
+}
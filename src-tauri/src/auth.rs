@@ -0,0 +1,151 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Window, WindowBuilder, WindowUrl};
+
+use crate::error::{Error, Result};
+
+/// Something a command needs permission to do.
+///
+/// Every command checks exactly one capability against the calling window
+/// before doing any work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Greet,
+    TodoRead,
+    TodoWrite,
+    TodoDelete,
+    WindowOpen,
+    JobRun,
+    JobRead,
+    DataRead,
+    DataWrite,
+    Notify,
+    /// Clearing the notification history, which every window shares.
+    NotifyClear,
+    SettingsRead,
+    SettingsWrite,
+    MenuUpdate,
+    NoteRead,
+    NoteWrite,
+    NoteDelete,
+    Search,
+    /// Cancelling in-flight commands; granted with anything cancellable.
+    RequestCancel,
+}
+
+/// Granted to the main window.
+pub const FULL_ACCESS: &[Capability] = &[
+    Capability::Greet,
+    Capability::TodoRead,
+    Capability::TodoWrite,
+    Capability::TodoDelete,
+    Capability::WindowOpen,
+    Capability::JobRun,
+    Capability::JobRead,
+    Capability::DataRead,
+    Capability::DataWrite,
+    Capability::Notify,
+    Capability::NotifyClear,
+    Capability::SettingsRead,
+    Capability::SettingsWrite,
+    Capability::MenuUpdate,
+    Capability::NoteRead,
+    Capability::NoteWrite,
+    Capability::NoteDelete,
+    Capability::Search,
+    Capability::RequestCancel,
+];
+
+/// Granted to popped-out viewer windows.
+pub const READ_ONLY: &[Capability] = &[
+    Capability::Greet,
+    Capability::TodoRead,
+    Capability::JobRead,
+    Capability::DataRead,
+    Capability::Notify,
+    Capability::SettingsRead,
+    Capability::NoteRead,
+    Capability::Search,
+    Capability::RequestCancel,
+];
+
+/// Capability sets keyed by window label.
+///
+/// Windows are granted their set when they are created and lose it when they
+/// are destroyed. A window without an entry can't call anything.
+#[derive(Default)]
+pub struct Grants(Mutex<HashMap<String, HashSet<Capability>>>);
+
+impl Grants {
+    pub fn grant(&self, label: &str, capabilities: &[Capability]) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(label.to_owned(), capabilities.iter().copied().collect());
+    }
+
+    pub fn revoke(&self, label: &str) {
+        self.0.lock().unwrap().remove(label);
+    }
+
+    pub fn capabilities(&self, label: &str) -> Vec<Capability> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(label)
+            .map(|caps| caps.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Fails with [`Error::Forbidden`] unless `window` holds `capability`.
+    pub fn require(&self, window: &Window, capability: Capability) -> Result<()> {
+        let granted = self
+            .0
+            .lock()
+            .unwrap()
+            .get(window.label())
+            .is_some_and(|caps| caps.contains(&capability));
+        if granted {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(format!(
+                "window `{}` lacks the `{:?}` capability",
+                window.label(),
+                capability
+            )))
+        }
+    }
+}
+
+/// Shorthand for `app.state::<Grants>().require(window, capability)`.
+pub fn require(window: &Window, capability: Capability) -> Result<()> {
+    window.state::<Grants>().require(window, capability)
+}
+
+/// Lists the capabilities of the calling window so the UI can hide what it
+/// isn't allowed to use.
+#[tauri::command]
+pub fn my_capabilities(window: Window) -> Vec<Capability> {
+    window.state::<Grants>().capabilities(window.label())
+}
+
+/// Opens a read-only window showing the same UI.
+#[tauri::command]
+pub fn open_viewer_window(app: AppHandle, window: Window) -> Result<String> {
+    require(&window, Capability::WindowOpen)?;
+    let label = format!("viewer-{}", uuid::Uuid::new_v4().simple());
+    // grant before building so the first command from the new window passes
+    app.state::<Grants>().grant(&label, READ_ONLY);
+    let built = WindowBuilder::new(&app, &label, WindowUrl::App("index.html".into()))
+        .title("tauri-leptos (read-only)")
+        .inner_size(800.0, 800.0)
+        .build();
+    if let Err(err) = built {
+        app.state::<Grants>().revoke(&label);
+        return Err(err.into());
+    }
+    Ok(label)
+}
@@ -0,0 +1,122 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::{State, Window};
+
+use crate::auth::{self, Capability};
+use crate::error::{Error, Result};
+
+/// How many cancellations for not-yet-registered requests are remembered.
+const MAX_EARLY_CANCELS: usize = 64;
+
+/// Shared flag long-running work polls to find out it should stop.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Fails with [`Error::Cancelled`] once cancelled; call between steps.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled("request was cancelled".into()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    running: HashMap<String, CancelToken>,
+    /// `cancel_request` can overtake the command it cancels.
+    early: VecDeque<String>,
+}
+
+/// Tokens for in-flight commands, keyed by the request id the UI sent along.
+#[derive(Default)]
+pub struct Cancellations(Mutex<Inner>);
+
+impl Cancellations {
+    /// Registers a request; the token is dropped from the registry when the
+    /// returned guard goes out of scope.
+    ///
+    /// Requests without an id can't be cancelled but still get a token, so
+    /// commands don't have to special-case them.
+    pub fn register<'a>(&'a self, request_id: Option<&str>) -> Registration<'a> {
+        let token = CancelToken::default();
+        let Some(request_id) = request_id else {
+            return Registration {
+                registry: self,
+                request_id: None,
+                token,
+            };
+        };
+        let mut inner = self.0.lock().unwrap();
+        if let Some(pos) = inner.early.iter().position(|id| id == request_id) {
+            inner.early.remove(pos);
+            token.cancel();
+        }
+        inner
+            .running
+            .insert(request_id.to_owned(), token.clone());
+        Registration {
+            registry: self,
+            request_id: Some(request_id.to_owned()),
+            token,
+        }
+    }
+
+    pub fn cancel(&self, request_id: &str) {
+        let mut inner = self.0.lock().unwrap();
+        match inner.running.get(request_id) {
+            Some(token) => token.cancel(),
+            None => {
+                if inner.early.len() == MAX_EARLY_CANCELS {
+                    inner.early.pop_front();
+                }
+                inner.early.push_back(request_id.to_owned());
+            }
+        }
+    }
+}
+
+pub struct Registration<'a> {
+    registry: &'a Cancellations,
+    request_id: Option<String>,
+    token: CancelToken,
+}
+
+impl Registration<'_> {
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        if let Some(request_id) = &self.request_id {
+            self.registry.0.lock().unwrap().running.remove(request_id);
+        }
+    }
+}
+
+/// Cancels the command that was called with `request_id`. Unknown ids are
+/// remembered briefly in case the command hasn't started yet.
+#[tauri::command]
+pub fn cancel_request(
+    window: Window,
+    cancellations: State<'_, Cancellations>,
+    request_id: String,
+) -> Result<()> {
+    auth::require(&window, Capability::RequestCancel)?;
+    cancellations.cancel(&request_id);
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use serde::Serialize;
+use tauri::Manager;
+
+#[derive(Clone, Serialize)]
+struct Changed<'a> {
+    keys: &'a [&'a str],
+}
+
+/// Tells every window that data behind the given query keys changed, so
+/// their caches refetch it. Keys are prefixes, usually a command name.
+pub fn emit<R: tauri::Runtime>(manager: &impl Manager<R>, keys: &[&str]) {
+    if let Err(err) = manager.emit_all("data://changed", Changed { keys }) {
+        eprintln!("failed to emit data://changed: {err}");
+    }
+}
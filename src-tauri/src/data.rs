@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use tauri::{State, Window};
+
+use crate::auth::{self, Capability};
+use crate::cancel::Cancellations;
+use crate::error::{Error, Result};
+
+/// Stand-in for slow work: takes a second, then returns `value * 10`, or
+/// `Invalid` if that overflows.
+///
+/// Stops early with `Cancelled` when the UI cancels `request_id`.
+#[tauri::command]
+pub async fn load_data(
+    window: Window,
+    cancellations: State<'_, Cancellations>,
+    value: i32,
+    request_id: Option<String>,
+) -> Result<i32> {
+    auth::require(&window, Capability::DataRead)?;
+    let registration = cancellations.register(request_id.as_deref());
+    for _ in 0..10 {
+        registration.token().check()?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    registration.token().check()?;
+    value
+        .checked_mul(10)
+        .ok_or_else(|| Error::Invalid(format!("{value} * 10 overflows")))
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+/// Errors returned from commands.
+///
+/// Serialized as `{ "kind": "...", "message": "..." }` so the UI can match on
+/// `kind` without parsing the message.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum Error {
+    /// The calling window was not granted the capability the command requires.
+    Forbidden(String),
+    NotFound(String),
+    /// The request itself is malformed, like an unparsable recurrence rule.
+    Invalid(String),
+    /// The caller cancelled the request before it finished.
+    Cancelled(String),
+    /// Rejected before any work was done; the UI may retry.
+    Busy(String),
+    Internal(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Forbidden(message) => write!(f, "forbidden: {message}"),
+            Error::NotFound(message) => write!(f, "not found: {message}"),
+            Error::Invalid(message) => write!(f, "invalid: {message}"),
+            Error::Cancelled(message) => write!(f, "cancelled: {message}"),
+            Error::Busy(message) => write!(f, "busy: {message}"),
+            Error::Internal(message) => write!(f, "internal error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<tauri::Error> for Error {
+    fn from(err: tauri::Error) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
+impl From<tantivy::TantivyError> for Error {
+    fn from(err: tantivy::TantivyError) -> Self {
+        Error::Internal(err.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,295 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::cancel::CancelToken;
+use crate::error::{Error, Result};
+use crate::tray;
+
+/// How many finished jobs are kept around for `list_jobs`.
+const MAX_FINISHED: usize = 50;
+/// `start_job` answers `Busy` once this many jobs are waiting for a worker.
+const MAX_QUEUED: usize = 32;
+
+/// The long-running tasks the UI can start.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", content = "args", rename_all = "snake_case")]
+pub enum JobSpec {
+    /// Stand-in for slow work: counts to ten, then returns `value * 10`.
+    LoadData { value: i32 },
+}
+
+impl JobSpec {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobSpec::LoadData { .. } => "load_data",
+        }
+    }
+
+    fn run(self, ctx: &JobContext) -> std::result::Result<Value, JobError> {
+        match self {
+            JobSpec::LoadData { value } => {
+                for step in 0..10 {
+                    ctx.checkpoint()?;
+                    thread::sleep(Duration::from_millis(100));
+                    ctx.progress((step + 1) as f32 / 10.0);
+                }
+                value
+                    .checked_mul(10)
+                    .map(Value::from)
+                    .ok_or_else(|| JobError::Failed(format!("{value} * 10 overflows")))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running { progress: f32 },
+    Completed { result: Value },
+    Failed { error: String },
+    Cancelled,
+}
+
+impl JobState {
+    fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed { .. } | JobState::Failed { .. } | JobState::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: Uuid,
+    pub kind: &'static str,
+    pub state: JobState,
+}
+
+enum JobError {
+    Cancelled,
+    Failed(String),
+}
+
+/// Handed to a running job so it can report progress and notice
+/// cancellation.
+struct JobContext {
+    id: Uuid,
+    app: AppHandle,
+    cancel: CancelToken,
+}
+
+impl JobContext {
+    fn progress(&self, progress: f32) {
+        self.app
+            .state::<Jobs>()
+            .transition(self.id, JobState::Running { progress });
+    }
+
+    /// Bails out with [`JobError::Cancelled`] once the job has been cancelled.
+    fn checkpoint(&self) -> std::result::Result<(), JobError> {
+        if self.cancel.is_cancelled() {
+            Err(JobError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+struct Entry {
+    info: JobInfo,
+    cancel: CancelToken,
+}
+
+struct Queued {
+    spec: JobSpec,
+    ctx: JobContext,
+}
+
+/// Runs [`JobSpec`]s on a fixed pool of worker threads.
+///
+/// Every state change is emitted to all windows as `job://progress`,
+/// `job://completed`, `job://failed` or `job://cancelled`, with the
+/// [`JobInfo`] as payload.
+pub struct Jobs {
+    app: AppHandle,
+    queue: Mutex<mpsc::Sender<Queued>>,
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl Jobs {
+    pub fn new(app: AppHandle, workers: usize) -> Self {
+        let (queue, rx) = mpsc::channel::<Queued>();
+        let rx = Arc::new(Mutex::new(rx));
+        for n in 0..workers {
+            let rx = Arc::clone(&rx);
+            thread::Builder::new()
+                .name(format!("job-worker-{n}"))
+                .spawn(move || loop {
+                    let next = rx.lock().unwrap().recv();
+                    let Ok(Queued { spec, ctx }) = next else {
+                        break;
+                    };
+                    Self::execute(spec, ctx);
+                })
+                .expect("failed to spawn job worker");
+        }
+        Self {
+            app,
+            queue: Mutex::new(queue),
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn execute(spec: JobSpec, ctx: JobContext) {
+        let jobs = ctx.app.state::<Jobs>();
+        if ctx.checkpoint().is_err() {
+            // cancelled while still queued; `cancel` already reported it
+            return;
+        }
+        jobs.transition(ctx.id, JobState::Running { progress: 0.0 });
+        let state = match spec.run(&ctx) {
+            Ok(result) => JobState::Completed { result },
+            Err(JobError::Cancelled) => JobState::Cancelled,
+            Err(JobError::Failed(error)) => JobState::Failed { error },
+        };
+        jobs.transition(ctx.id, state);
+    }
+
+    pub fn submit(&self, spec: JobSpec) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        let cancel = CancelToken::default();
+        let mut entries = self.entries.lock().unwrap();
+        let queued = entries
+            .iter()
+            .filter(|entry| matches!(entry.info.state, JobState::Queued))
+            .count();
+        if queued >= MAX_QUEUED {
+            return Err(Error::Busy(format!("{queued} jobs are already queued")));
+        }
+        entries.push(Entry {
+            info: JobInfo {
+                id,
+                kind: spec.kind(),
+                state: JobState::Queued,
+            },
+            cancel: cancel.clone(),
+        });
+        drop(entries);
+        let ctx = JobContext {
+            id,
+            app: self.app.clone(),
+            cancel,
+        };
+        self.queue
+            .lock()
+            .unwrap()
+            .send(Queued { spec, ctx })
+            .expect("job workers have stopped");
+        self.prune();
+        tray::set_job_count(&self.app, self.pending());
+        Ok(id)
+    }
+
+    /// Asks a job to stop. Queued jobs are cancelled immediately, running ones
+    /// at their next checkpoint.
+    pub fn cancel(&self, id: Uuid) -> Result<()> {
+        let queued = {
+            let entries = self.entries.lock().unwrap();
+            let entry = entries
+                .iter()
+                .find(|entry| entry.info.id == id)
+                .ok_or_else(|| Error::NotFound(format!("job {id}")))?;
+            entry.cancel.cancel();
+            matches!(entry.info.state, JobState::Queued)
+        };
+        if queued {
+            self.transition(id, JobState::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// How many jobs are queued or running.
+    pub fn pending(&self) -> usize {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .filter(|entry| !entry.info.state.is_finished())
+            .count()
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().map(|entry| entry.info.clone()).collect()
+    }
+
+    /// Records a new state and emits the matching event. Finished jobs never
+    /// change state again.
+    fn transition(&self, id: Uuid, state: JobState) {
+        let info = {
+            let mut entries = self.entries.lock().unwrap();
+            let Some(entry) = entries.iter_mut().find(|entry| entry.info.id == id) else {
+                return;
+            };
+            if entry.info.state.is_finished() {
+                return;
+            }
+            entry.info.state = state;
+            entry.info.clone()
+        };
+        let event = match info.state {
+            JobState::Queued | JobState::Running { .. } => "job://progress",
+            JobState::Completed { .. } => "job://completed",
+            JobState::Failed { .. } => "job://failed",
+            JobState::Cancelled => "job://cancelled",
+        };
+        if let Err(err) = self.app.emit_all(event, &info) {
+            eprintln!("failed to emit {event}: {err}");
+        }
+        tray::set_job_count(&self.app, self.pending());
+    }
+
+    fn prune(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        let finished = entries
+            .iter()
+            .filter(|entry| entry.info.state.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED);
+        entries.retain(|entry| {
+            if excess > 0 && entry.info.state.is_finished() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub fn start_job(window: Window, jobs: State<'_, Jobs>, spec: JobSpec) -> Result<Uuid> {
+    auth::require(&window, Capability::JobRun)?;
+    jobs.submit(spec)
+}
+
+#[tauri::command]
+pub fn cancel_job(window: Window, jobs: State<'_, Jobs>, id: Uuid) -> Result<()> {
+    auth::require(&window, Capability::JobRun)?;
+    jobs.cancel(id)
+}
+
+#[tauri::command]
+pub fn list_jobs(window: Window, jobs: State<'_, Jobs>) -> Result<Vec<JobInfo>> {
+    auth::require(&window, Capability::JobRead)?;
+    Ok(jobs.list())
+}
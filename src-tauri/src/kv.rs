@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde_json::{json, Value};
+use tauri::{State, Window};
+
+use crate::auth::{self, Capability};
+use crate::changes;
+use crate::error::{Error, Result};
+use crate::storage::Storage;
+
+const DOCUMENT: &str = "kv";
+const MAX_KEY_LEN: usize = 200;
+
+/// The query key of `kv_get` for `key`, so a change only refetches that
+/// value.
+fn query_key(key: &str) -> String {
+    format!("kv_get:{}", json!({ "key": key }))
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(Error::Invalid(format!(
+            "key `{key}`; keys must be 1 to {MAX_KEY_LEN} bytes"
+        )));
+    }
+    Ok(())
+}
+
+/// JSON values by key, for UI state that has no store of its own. Saved to
+/// storage on every change.
+pub struct KeyValueStore {
+    storage: Storage,
+    values: Mutex<BTreeMap<String, Value>>,
+}
+
+impl KeyValueStore {
+    pub fn load(storage: Storage) -> Result<Self> {
+        let values = storage.load(DOCUMENT)?.unwrap_or_default();
+        Ok(Self {
+            storage,
+            values: Mutex::new(values),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    /// Returns whether the value changed.
+    pub fn set(&self, key: String, value: Value) -> Result<bool> {
+        let mut values = self.values.lock().unwrap();
+        if values.get(&key) == Some(&value) {
+            return Ok(false);
+        }
+        // kept only once saved, so retrying after a failed save saves again
+        let mut changed = values.clone();
+        changed.insert(key, value);
+        self.storage.save(DOCUMENT, &changed)?;
+        *values = changed;
+        Ok(true)
+    }
+
+    /// Returns whether there was a value.
+    pub fn remove(&self, key: &str) -> Result<bool> {
+        let mut values = self.values.lock().unwrap();
+        if !values.contains_key(key) {
+            return Ok(false);
+        }
+        let mut changed = values.clone();
+        changed.remove(key);
+        self.storage.save(DOCUMENT, &changed)?;
+        *values = changed;
+        Ok(true)
+    }
+}
+
+/// `None` if the key was never set.
+#[tauri::command]
+pub fn kv_get(
+    window: Window,
+    store: State<'_, KeyValueStore>,
+    key: String,
+) -> Result<Option<Value>> {
+    auth::require(&window, Capability::DataRead)?;
+    Ok(store.get(&key))
+}
+
+#[tauri::command]
+pub fn kv_set(
+    window: Window,
+    store: State<'_, KeyValueStore>,
+    key: String,
+    value: Value,
+) -> Result<()> {
+    auth::require(&window, Capability::DataWrite)?;
+    validate_key(&key)?;
+    let query = query_key(&key);
+    // saving what's already saved shouldn't make every window refetch
+    if store.set(key, value)? {
+        changes::emit(&window, &[&query]);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn kv_remove(window: Window, store: State<'_, KeyValueStore>, key: String) -> Result<()> {
+    auth::require(&window, Capability::DataWrite)?;
+    if store.remove(&key)? {
+        changes::emit(&window, &[&query_key(&key)]);
+    }
+    Ok(())
+}
@@ -1,15 +1,128 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod auth;
+mod cancel;
+mod changes;
+mod data;
+mod error;
+mod jobs;
+mod kv;
+mod menu;
+mod notes;
+mod notifications;
+mod outbox;
+mod recurrence;
+mod reminders;
+mod search;
+mod settings;
+mod storage;
+mod todos;
+mod tray;
+mod unsaved;
+
+use auth::{Capability, Grants};
+use tauri::{Manager, Window, WindowEvent};
+
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+fn greet(window: Window, name: &str) -> error::Result<String> {
+    auth::require(&window, Capability::Greet)?;
+    Ok(format!("Hello, {}! You've been greeted from Rust!", name))
 }
 
 fn main() {
+    let context = tauri::generate_context!();
+    // the menu's accelerators come from the settings, and it's built before
+    // the app is
+    let data_dir = tauri::api::path::app_data_dir(context.config())
+        .expect("no app data directory on this platform");
+    let storage = storage::Storage::open(data_dir).expect("failed to open the app data");
+    let settings =
+        settings::SettingsStore::load(storage.clone()).expect("failed to load the settings");
+    let (menu, accelerators) = menu::build(&settings.get());
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![greet])
-        .run(tauri::generate_context!())
+        .menu(menu)
+        .on_menu_event(menu::handle_event)
+        .system_tray(tray::build())
+        .on_system_tray_event(tray::handle_event)
+        .manage(Grants::default())
+        .manage(cancel::Cancellations::default())
+        .manage(unsaved::Unsaved::default())
+        .manage(settings)
+        .manage(accelerators)
+        .setup(move |app| {
+            // windows from tauri.conf.json exist before setup runs
+            let grants = app.state::<Grants>();
+            for label in app.windows().keys() {
+                grants.grant(label, auth::FULL_ACCESS);
+            }
+            app.manage(jobs::Jobs::new(app.handle(), 4));
+
+            let search = search::SearchIndex::new()?;
+            app.manage(todos::Todos::load(storage.clone(), search.clone())?);
+            app.manage(notifications::Notifications::load(storage.clone())?);
+            app.manage(kv::KeyValueStore::load(storage.clone())?);
+            app.manage(notes::Notes::load(storage.clone(), search.clone())?);
+            app.manage(search);
+            app.manage(reminders::Reminders::load(storage.clone())?);
+            app.manage(outbox::Outbox::load(storage)?);
+            outbox::Outbox::start_replay(app.handle());
+            reminders::Reminders::start(app.handle());
+            Ok(())
+        })
+        .on_window_event(|event| match event.event() {
+            WindowEvent::CloseRequested { api, .. } => {
+                // hiding to the tray keeps unsaved changes, so only ask otherwise
+                if tray::close_to_tray(event.window()) || unsaved::confirm_close(event.window()) {
+                    api.prevent_close();
+                }
+            }
+            WindowEvent::Destroyed => {
+                let label = event.window().label();
+                event.window().state::<Grants>().revoke(label);
+                event.window().state::<unsaved::Unsaved>().forget(label);
+            }
+            _ => {}
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            auth::my_capabilities,
+            auth::open_viewer_window,
+            cancel::cancel_request,
+            data::load_data,
+            kv::kv_get,
+            kv::kv_set,
+            kv::kv_remove,
+            todos::list_todos,
+            jobs::start_job,
+            jobs::cancel_job,
+            jobs::list_jobs,
+            outbox::enqueue_mutation,
+            outbox::list_outbox,
+            reminders::list_reminders,
+            reminders::snooze_reminder,
+            reminders::dismiss_reminder,
+            notifications::list_notifications,
+            notifications::record_notification,
+            notifications::clear_notifications,
+            settings::get_settings,
+            settings::update_settings,
+            menu::menu_accelerators,
+            menu::set_menu_state,
+            notes::list_notes,
+            notes::read_note,
+            notes::create_note,
+            notes::save_note,
+            notes::rename_note,
+            notes::delete_note,
+            notes::list_note_revisions,
+            notes::restore_note_revision,
+            search::search,
+            unsaved::set_unsaved,
+            unsaved::close_window,
+            unsaved::quit_app
+        ])
+        .run(context)
         .expect("error while running tauri application");
 }
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{CustomMenuItem, Menu, MenuItem, State, Submenu, Window, WindowMenuEvent};
+
+use crate::auth::{self, Capability};
+use crate::error::{Error, Result};
+use crate::settings::Settings;
+use crate::unsaved;
+
+/// Handled here rather than by the UI, but not natively, so unsaved changes
+/// are asked about first.
+const QUIT: &str = "quit";
+
+/// Menu items the UI handles, sent to it as `menu://action` events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuAction {
+    AddTodo,
+    OpenViewer,
+    Undo,
+    Redo,
+    TogglePalette,
+    ShowShortcuts,
+    ToggleTheme,
+    About,
+}
+
+impl MenuAction {
+    const ALL: [MenuAction; 8] = [
+        MenuAction::AddTodo,
+        MenuAction::OpenViewer,
+        MenuAction::Undo,
+        MenuAction::Redo,
+        MenuAction::TogglePalette,
+        MenuAction::ShowShortcuts,
+        MenuAction::ToggleTheme,
+        MenuAction::About,
+    ];
+
+    /// The menu item id.
+    fn id(self) -> &'static str {
+        match self {
+            MenuAction::AddTodo => "add_todo",
+            MenuAction::OpenViewer => "open_viewer",
+            MenuAction::Undo => "undo",
+            MenuAction::Redo => "redo",
+            MenuAction::TogglePalette => "toggle_palette",
+            MenuAction::ShowShortcuts => "show_shortcuts",
+            MenuAction::ToggleTheme => "toggle_theme",
+            MenuAction::About => "about",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    /// The id of the UI command the item runs, which shortcut overrides are
+    /// keyed by.
+    fn command(self) -> &'static str {
+        match self {
+            MenuAction::AddTodo => "todo.add",
+            MenuAction::OpenViewer => "window.open_viewer",
+            MenuAction::Undo => "edit.undo",
+            MenuAction::Redo => "edit.redo",
+            MenuAction::TogglePalette => "palette.toggle",
+            MenuAction::ShowShortcuts => "shortcuts.show",
+            MenuAction::ToggleTheme => "theme.toggle",
+            MenuAction::About => "help.about",
+        }
+    }
+
+    /// The UI's default shortcut for the command. Undo and Redo have none,
+    /// so Ctrl+Z keeps working in text fields.
+    fn default_shortcut(self) -> Option<&'static str> {
+        match self {
+            MenuAction::AddTodo => Some("Alt+N"),
+            MenuAction::TogglePalette => Some("Ctrl+K"),
+            MenuAction::ToggleTheme => Some("Ctrl+Shift+L"),
+            _ => None,
+        }
+    }
+
+    /// The shortcut once the user's overrides are applied.
+    fn shortcut(self, settings: &Settings) -> Option<String> {
+        match settings.shortcuts.get(self.command()) {
+            Some(chord) => chord.clone(),
+            None => self.default_shortcut().map(str::to_owned),
+        }
+    }
+
+    fn item(self, shortcut: Option<&str>) -> CustomMenuItem {
+        let title = match self {
+            MenuAction::AddTodo => "New Todo",
+            MenuAction::OpenViewer => "Open Read-Only Window",
+            MenuAction::Undo => "Undo",
+            MenuAction::Redo => "Redo",
+            MenuAction::TogglePalette => "Command Palette",
+            MenuAction::ShowShortcuts => "Keyboard Shortcuts",
+            MenuAction::ToggleTheme => "Dark Theme",
+            MenuAction::About => "About",
+        };
+        let item = CustomMenuItem::new(self.id(), title);
+        match shortcut {
+            Some(shortcut) => item.accelerator(accelerator(shortcut)),
+            None => item,
+        }
+    }
+}
+
+/// A UI shortcut like `Ctrl+Shift+K` as an accelerator. The UI counts Cmd as
+/// Ctrl, so Ctrl becomes `CmdOrCtrl`.
+fn accelerator(shortcut: &str) -> String {
+    let (modifiers, key) = match shortcut.rsplit_once('+') {
+        // `Ctrl++` binds the plus key
+        Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "Plus"),
+        Some((modifiers, key)) => (modifiers, key),
+        None => ("", shortcut),
+    };
+    let mut parts = modifiers
+        .split('+')
+        .filter(|modifier| !modifier.is_empty())
+        .map(|modifier| match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" | "cmd" | "meta" => "CmdOrCtrl",
+            "alt" | "option" => "Alt",
+            "shift" => "Shift",
+            _ => modifier,
+        })
+        .collect::<Vec<_>>();
+    parts.push(key);
+    parts.join("+")
+}
+
+/// The shortcuts the menu's accelerators were built from, by command id.
+///
+/// Menu items can't be given new accelerators once built, so a shortcut
+/// rebound later only reaches the menu on the next start.
+pub struct Accelerators(BTreeMap<String, String>);
+
+/// The main window's menu bar, with accelerators for the commands' shortcuts
+/// in `settings`.
+pub fn build(settings: &Settings) -> (Menu, Accelerators) {
+    let mut accelerators = BTreeMap::new();
+    let mut item = |action: MenuAction| {
+        let shortcut = action.shortcut(settings);
+        if let Some(shortcut) = &shortcut {
+            accelerators.insert(action.command().to_owned(), shortcut.clone());
+        }
+        action.item(shortcut.as_deref())
+    };
+    let file = Menu::new()
+        .add_item(item(MenuAction::AddTodo))
+        .add_item(item(MenuAction::OpenViewer))
+        .add_native_item(MenuItem::Separator)
+        .add_item(CustomMenuItem::new(QUIT, "Quit").accelerator("CmdOrCtrl+Q"));
+    let edit = Menu::new()
+        .add_item(item(MenuAction::Undo))
+        .add_item(item(MenuAction::Redo))
+        .add_native_item(MenuItem::Separator)
+        .add_native_item(MenuItem::Cut)
+        .add_native_item(MenuItem::Copy)
+        .add_native_item(MenuItem::Paste)
+        .add_native_item(MenuItem::SelectAll);
+    let view = Menu::new()
+        .add_item(item(MenuAction::TogglePalette))
+        .add_item(item(MenuAction::ShowShortcuts))
+        .add_native_item(MenuItem::Separator)
+        .add_item(item(MenuAction::ToggleTheme));
+    let help = Menu::new().add_item(item(MenuAction::About));
+    let menu = Menu::new()
+        .add_submenu(Submenu::new("File", file))
+        .add_submenu(Submenu::new("Edit", edit))
+        .add_submenu(Submenu::new("View", view))
+        .add_submenu(Submenu::new("Help", help));
+    (menu, Accelerators(accelerators))
+}
+
+/// Forwards clicks on custom items to the window whose menu was clicked.
+pub fn handle_event(event: WindowMenuEvent) {
+    if event.menu_item_id() == QUIT {
+        unsaved::quit(&event.window().app_handle());
+        return;
+    }
+    let Some(action) = MenuAction::from_id(event.menu_item_id()) else {
+        return;
+    };
+    if let Err(err) = event.window().emit("menu://action", action) {
+        eprintln!("failed to emit menu://action: {err}");
+    }
+}
+
+/// The shortcuts the menu handles itself, by command id, so the UI leaves
+/// those key presses to it instead of running the command a second time.
+#[tauri::command]
+pub fn menu_accelerators(
+    window: Window,
+    accelerators: State<'_, Accelerators>,
+) -> Result<BTreeMap<String, String>> {
+    auth::require(&window, Capability::MenuUpdate)?;
+    Ok(accelerators.0.clone())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MenuItemState {
+    action: MenuAction,
+    enabled: bool,
+    /// Only for items that toggle something.
+    #[serde(default)]
+    checked: Option<bool>,
+}
+
+#[tauri::command]
+pub fn set_menu_state(window: Window, items: Vec<MenuItemState>) -> Result<()> {
+    auth::require(&window, Capability::MenuUpdate)?;
+    let menu = window.menu_handle();
+    for state in items {
+        let item = menu
+            .try_get_item(state.action.id())
+            .ok_or_else(|| Error::NotFound(format!("menu item {}", state.action.id())))?;
+        item.set_enabled(state.enabled)?;
+        if let Some(checked) = state.checked {
+            item.set_selected(checked)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,323 @@
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::changes;
+use crate::error::{Error, Result};
+use crate::search::{Entry, Kind, SearchIndex};
+use crate::storage::Storage;
+
+/// Lists every note; the bodies are kept in one markdown file per note.
+const INDEX: &str = "notes";
+/// Queries that may be affected by any change to a note.
+const CHANGED_QUERIES: &[&str] = &["list_notes", "read_note", "list_note_revisions", "search"];
+/// Older revisions of a note are dropped beyond this many.
+const MAX_REVISIONS: usize = 50;
+/// Saves this close to the start of the latest revision update it instead of
+/// starting another, so autosaving while typing doesn't flood the history.
+const REVISION_INTERVAL_MS: f64 = 5.0 * 60.0 * 1000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteMeta {
+    pub id: Uuid,
+    pub title: String,
+    /// Milliseconds since the Unix epoch.
+    pub updated_at: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Note {
+    #[serde(flatten)]
+    pub meta: NoteMeta,
+    /// Markdown.
+    pub body: String,
+}
+
+/// A saved version of a note's body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub id: Uuid,
+    /// When the first save in this revision happened, in milliseconds since
+    /// the Unix epoch.
+    pub started_at: f64,
+    /// When the last save in this revision happened.
+    pub saved_at: f64,
+    pub body: String,
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64() * 1000.0)
+}
+
+fn file_name(id: Uuid) -> String {
+    format!("{id}.md")
+}
+
+/// The JSON document holding a note's revisions, oldest first.
+fn revisions_name(id: Uuid) -> String {
+    format!("{id}.revisions")
+}
+
+fn entry<'a>(meta: &'a NoteMeta, body: &'a str) -> Entry<'a> {
+    Entry {
+        id: meta.id,
+        kind: Kind::Note,
+        title: &meta.title,
+        body,
+    }
+}
+
+/// Markdown notes, saved to storage and indexed for search after every
+/// change.
+pub struct Notes {
+    storage: Storage,
+    files: Storage,
+    search: SearchIndex,
+    index: Mutex<Vec<NoteMeta>>,
+}
+
+impl Notes {
+    pub fn load(storage: Storage, search: SearchIndex) -> Result<Self> {
+        let files = storage.subdir("notes")?;
+        let index: Vec<NoteMeta> = storage.load(INDEX)?.unwrap_or_default();
+        let bodies = index
+            .iter()
+            .map(|meta| Ok(files.read_file(&file_name(meta.id))?.unwrap_or_default()))
+            .collect::<Result<Vec<_>>>()?;
+        search.index_all(index.iter().zip(&bodies).map(|(meta, body)| entry(meta, body)));
+        Ok(Self {
+            storage,
+            files,
+            search,
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Most recently updated first.
+    pub fn list(&self) -> Vec<NoteMeta> {
+        let mut notes = self.index.lock().unwrap().clone();
+        notes.sort_by(|a, b| b.updated_at.total_cmp(&a.updated_at));
+        notes
+    }
+
+    pub fn read(&self, id: Uuid) -> Result<Note> {
+        let index = self.index.lock().unwrap();
+        let meta = index
+            .iter()
+            .find(|note| note.id == id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("note {id}")))?;
+        let body = self.files.read_file(&file_name(id))?.unwrap_or_default();
+        Ok(Note { meta, body })
+    }
+
+    pub fn create(&self, title: String) -> Result<NoteMeta> {
+        let mut index = self.index.lock().unwrap();
+        let meta = NoteMeta {
+            id: Uuid::new_v4(),
+            title,
+            updated_at: now(),
+        };
+        self.files.write_file(&file_name(meta.id), "")?;
+        // kept and indexed only once saved, so a failed save changes nothing
+        let saved = index.iter().chain([&meta]).collect::<Vec<_>>();
+        self.storage.save(INDEX, &saved)?;
+        index.push(meta.clone());
+        self.search.index(entry(&meta, ""));
+        Ok(meta)
+    }
+
+    /// Applies `change` to a copy of the note's entry, marks it updated `at`
+    /// and keeps it once the index is saved.
+    fn update(
+        &self,
+        id: Uuid,
+        at: f64,
+        change: impl FnOnce(&mut NoteMeta) -> Result<()>,
+    ) -> Result<NoteMeta> {
+        let mut index = self.index.lock().unwrap();
+        let position = index
+            .iter()
+            .position(|note| note.id == id)
+            .ok_or_else(|| Error::NotFound(format!("note {id}")))?;
+        let mut changed = index.clone();
+        let meta = &mut changed[position];
+        change(meta)?;
+        meta.updated_at = at;
+        let meta = meta.clone();
+        self.storage.save(INDEX, &changed)?;
+        *index = changed;
+        Ok(meta)
+    }
+
+    /// Saves the body and records it in the note's history. A `checkpoint`
+    /// always starts a new revision.
+    pub fn save(&self, id: Uuid, body: &str, checkpoint: bool) -> Result<NoteMeta> {
+        let at = now();
+        let meta = self.update(id, at, |_| {
+            self.files.write_file(&file_name(id), body)?;
+            self.record_revision(id, body, at, checkpoint)
+        })?;
+        self.search.index(entry(&meta, body));
+        Ok(meta)
+    }
+
+    fn record_revision(&self, id: Uuid, body: &str, at: f64, checkpoint: bool) -> Result<()> {
+        let name = revisions_name(id);
+        let mut revisions: Vec<Revision> = self.files.load(&name)?.unwrap_or_default();
+        match revisions.last_mut() {
+            Some(latest) if !checkpoint && at - latest.started_at < REVISION_INTERVAL_MS => {
+                latest.saved_at = at;
+                latest.body = body.to_owned();
+            }
+            _ => revisions.push(Revision {
+                id: Uuid::new_v4(),
+                started_at: at,
+                saved_at: at,
+                body: body.to_owned(),
+            }),
+        }
+        let excess = revisions.len().saturating_sub(MAX_REVISIONS);
+        revisions.drain(..excess);
+        self.files.save(&name, &revisions)
+    }
+
+    /// Newest first.
+    pub fn revisions(&self, id: Uuid) -> Result<Vec<Revision>> {
+        // held so a concurrent save can't be half written
+        let index = self.index.lock().unwrap();
+        if !index.iter().any(|note| note.id == id) {
+            return Err(Error::NotFound(format!("note {id}")));
+        }
+        let mut revisions: Vec<Revision> =
+            self.files.load(&revisions_name(id))?.unwrap_or_default();
+        revisions.reverse();
+        Ok(revisions)
+    }
+
+    /// Makes an earlier revision the current body, as a new revision so the
+    /// restore itself can be undone.
+    pub fn restore(&self, id: Uuid, revision: Uuid) -> Result<NoteMeta> {
+        let body = self
+            .revisions(id)?
+            .into_iter()
+            .find(|r| r.id == revision)
+            .ok_or_else(|| Error::NotFound(format!("revision {revision} of note {id}")))?
+            .body;
+        self.save(id, &body, true)
+    }
+
+    pub fn rename(&self, id: Uuid, title: String) -> Result<NoteMeta> {
+        let meta = self.update(id, now(), |meta| {
+            meta.title = title;
+            Ok(())
+        })?;
+        let body = self.files.read_file(&file_name(id))?.unwrap_or_default();
+        self.search.index(entry(&meta, &body));
+        Ok(meta)
+    }
+
+    /// Returns whether a note was removed.
+    pub fn remove(&self, id: Uuid) -> Result<bool> {
+        let mut index = self.index.lock().unwrap();
+        let kept = index
+            .iter()
+            .filter(|note| note.id != id)
+            .cloned()
+            .collect::<Vec<_>>();
+        if kept.len() == index.len() {
+            return Ok(false);
+        }
+        self.storage.save(INDEX, &kept)?;
+        *index = kept;
+        self.files.remove_file(&file_name(id))?;
+        self.files.remove(&revisions_name(id))?;
+        self.search.remove(id);
+        Ok(true)
+    }
+}
+
+#[tauri::command]
+pub fn list_notes(window: Window, notes: State<'_, Notes>) -> Result<Vec<NoteMeta>> {
+    auth::require(&window, Capability::NoteRead)?;
+    Ok(notes.list())
+}
+
+#[tauri::command]
+pub fn read_note(window: Window, notes: State<'_, Notes>, id: Uuid) -> Result<Note> {
+    auth::require(&window, Capability::NoteRead)?;
+    notes.read(id)
+}
+
+#[tauri::command]
+pub fn create_note(window: Window, notes: State<'_, Notes>, title: String) -> Result<NoteMeta> {
+    auth::require(&window, Capability::NoteWrite)?;
+    let meta = notes.create(title)?;
+    changes::emit(&window, CHANGED_QUERIES);
+    Ok(meta)
+}
+
+#[tauri::command]
+pub fn save_note(
+    window: Window,
+    notes: State<'_, Notes>,
+    id: Uuid,
+    body: String,
+) -> Result<NoteMeta> {
+    auth::require(&window, Capability::NoteWrite)?;
+    let meta = notes.save(id, &body, false)?;
+    changes::emit(&window, CHANGED_QUERIES);
+    Ok(meta)
+}
+
+#[tauri::command]
+pub fn list_note_revisions(
+    window: Window,
+    notes: State<'_, Notes>,
+    id: Uuid,
+) -> Result<Vec<Revision>> {
+    auth::require(&window, Capability::NoteRead)?;
+    notes.revisions(id)
+}
+
+#[tauri::command]
+pub fn restore_note_revision(
+    window: Window,
+    notes: State<'_, Notes>,
+    id: Uuid,
+    revision: Uuid,
+) -> Result<NoteMeta> {
+    auth::require(&window, Capability::NoteWrite)?;
+    let meta = notes.restore(id, revision)?;
+    changes::emit(&window, CHANGED_QUERIES);
+    Ok(meta)
+}
+
+#[tauri::command]
+pub fn rename_note(
+    window: Window,
+    notes: State<'_, Notes>,
+    id: Uuid,
+    title: String,
+) -> Result<NoteMeta> {
+    auth::require(&window, Capability::NoteWrite)?;
+    let meta = notes.rename(id, title)?;
+    changes::emit(&window, CHANGED_QUERIES);
+    Ok(meta)
+}
+
+#[tauri::command]
+pub fn delete_note(window: Window, notes: State<'_, Notes>, id: Uuid) -> Result<()> {
+    auth::require(&window, Capability::NoteDelete)?;
+    if !notes.remove(id)? {
+        return Err(Error::NotFound(format!("note {id}")));
+    }
+    changes::emit(&window, CHANGED_QUERIES);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::changes;
+use crate::error::Result;
+use crate::storage::Storage;
+
+const DOCUMENT: &str = "notifications";
+/// How many notifications the history keeps.
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Chosen by the UI; recording the same id twice keeps one entry.
+    pub id: Uuid,
+    pub level: Level,
+    pub message: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: f64,
+}
+
+/// The most recent notifications shown in any window, oldest first.
+pub struct Notifications {
+    storage: Storage,
+    history: Mutex<VecDeque<Notification>>,
+}
+
+impl Notifications {
+    pub fn load(storage: Storage) -> Result<Self> {
+        let history = storage.load(DOCUMENT)?.unwrap_or_default();
+        Ok(Self {
+            storage,
+            history: Mutex::new(history),
+        })
+    }
+
+    pub fn list(&self) -> Vec<Notification> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn record(&self, notification: Notification) -> Result<()> {
+        let mut history = self.history.lock().unwrap();
+        if history.iter().any(|n| n.id == notification.id) {
+            return Ok(());
+        }
+        if history.len() == MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(notification);
+        self.storage.save(DOCUMENT, &*history)
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        let mut history = self.history.lock().unwrap();
+        history.clear();
+        self.storage.save(DOCUMENT, &*history)
+    }
+}
+
+#[tauri::command]
+pub fn list_notifications(
+    window: Window,
+    notifications: State<'_, Notifications>,
+) -> Result<Vec<Notification>> {
+    auth::require(&window, Capability::Notify)?;
+    Ok(notifications.list())
+}
+
+#[tauri::command]
+pub fn record_notification(
+    window: Window,
+    notifications: State<'_, Notifications>,
+    notification: Notification,
+) -> Result<()> {
+    auth::require(&window, Capability::Notify)?;
+    notifications.record(notification)?;
+    changes::emit(&window, &["list_notifications"]);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_notifications(window: Window, notifications: State<'_, Notifications>) -> Result<()> {
+    auth::require(&window, Capability::NotifyClear)?;
+    notifications.clear()?;
+    changes::emit(&window, &["list_notifications"]);
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::changes;
+use crate::error::{Error, Result};
+use crate::reminders::Reminders;
+use crate::storage::Storage;
+use crate::todos::{self, Status, TodoDetails, Todos};
+
+const DOCUMENT: &str = "outbox";
+/// How many idempotency keys of applied mutations are remembered.
+const MAX_APPLIED_KEYS: usize = 1000;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A change the UI wants made, applied later by the replay thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Mutation {
+    AddTodo {
+        id: Uuid,
+        text: String,
+        #[serde(default)]
+        details: TodoDetails,
+    },
+    UpdateTodo {
+        id: Uuid,
+        text: String,
+        details: TodoDetails,
+    },
+    /// `next_id` is the id of the next occurrence, if the todo recurs.
+    CompleteTodo { id: Uuid, next_id: Uuid },
+    ReopenTodo { id: Uuid, next_id: Uuid },
+    SetTodoStatus {
+        id: Uuid,
+        status: Status,
+        next_id: Uuid,
+    },
+    DeleteTodo { id: Uuid },
+}
+
+impl Mutation {
+    fn capability(&self) -> Capability {
+        match self {
+            Mutation::AddTodo { .. }
+            | Mutation::UpdateTodo { .. }
+            | Mutation::CompleteTodo { .. }
+            | Mutation::ReopenTodo { .. }
+            | Mutation::SetTodoStatus { .. } => Capability::TodoWrite,
+            Mutation::DeleteTodo { .. } => Capability::TodoDelete,
+        }
+    }
+
+    /// Rejects mutations that could never apply, before they are queued.
+    fn validate(&self) -> Result<()> {
+        match self {
+            Mutation::AddTodo { details, .. } | Mutation::UpdateTodo { details, .. } => {
+                details.validate()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Safe to repeat: adding an existing id, deleting a missing one, or
+    /// setting what's already set is a no-op.
+    fn apply(&self, app: &AppHandle) -> Result<()> {
+        let todos = app.state::<Todos>();
+        match self {
+            Mutation::AddTodo { id, text, details } => {
+                todos.insert(*id, text.clone(), details.clone())
+            }
+            Mutation::UpdateTodo { id, text, details } => {
+                todos.update(*id, text.clone(), details.clone())
+            }
+            Mutation::CompleteTodo { id, next_id } => todos.complete(*id, *next_id),
+            Mutation::ReopenTodo { id, next_id } => todos.reopen(*id, *next_id),
+            Mutation::SetTodoStatus {
+                id,
+                status,
+                next_id,
+            } => todos.set_status(*id, *status, *next_id),
+            Mutation::DeleteTodo { id } => todos.remove(*id).map(|_| ()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    /// Chosen by the UI; enqueueing the same key twice keeps one entry.
+    pub idempotency_key: Uuid,
+    pub mutation: Mutation,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Queue {
+    pending: VecDeque<OutboxEntry>,
+    applied: VecDeque<Uuid>,
+}
+
+#[derive(Clone, Serialize)]
+struct Failed<'a> {
+    entry: &'a OutboxEntry,
+    error: String,
+}
+
+/// Mutations waiting to be applied, in the order they were enqueued.
+///
+/// The queue is saved before `enqueue_mutation` returns, so accepted
+/// mutations survive a crash or restart. Changes to the queue are emitted as
+/// `outbox://changed` with the pending entries; mutations that can never be
+/// applied are dropped and emitted as `outbox://failed`.
+pub struct Outbox {
+    storage: Storage,
+    queue: Mutex<Queue>,
+    wake: Condvar,
+}
+
+impl Outbox {
+    pub fn load(storage: Storage) -> Result<Self> {
+        let queue = storage.load(DOCUMENT)?.unwrap_or_default();
+        Ok(Self {
+            storage,
+            queue: Mutex::new(queue),
+            wake: Condvar::new(),
+        })
+    }
+
+    pub fn pending(&self) -> Vec<OutboxEntry> {
+        self.queue.lock().unwrap().pending.iter().cloned().collect()
+    }
+
+    pub fn enqueue(&self, entry: OutboxEntry) -> Result<()> {
+        let mut queue = self.queue.lock().unwrap();
+        let key = entry.idempotency_key;
+        let known = queue.applied.contains(&key)
+            || queue.pending.iter().any(|e| e.idempotency_key == key);
+        if !known {
+            queue.pending.push_back(entry);
+            self.storage.save(DOCUMENT, &*queue)?;
+            self.wake.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Applies pending mutations one at a time on a background thread.
+    ///
+    /// A mutation that fails with a transient error stays at the head of the
+    /// queue and is retried with backoff, so later mutations never overtake
+    /// it.
+    pub fn start_replay(app: AppHandle) {
+        thread::Builder::new()
+            .name("outbox-replay".into())
+            .spawn(move || loop {
+                let outbox = app.state::<Outbox>();
+                let entry = {
+                    let mut queue = outbox.queue.lock().unwrap();
+                    while queue.pending.is_empty() {
+                        queue = outbox.wake.wait(queue).unwrap();
+                    }
+                    queue.pending[0].clone()
+                };
+                let mut retry_after = None;
+                match entry.mutation.apply(&app) {
+                    Ok(()) => {
+                        outbox.finish(&entry);
+                        app.state::<Reminders>().reschedule();
+                        changes::emit(&app, todos::CHANGED_QUERIES);
+                    }
+                    Err(err @ (Error::Busy(_) | Error::Internal(_))) => {
+                        retry_after = Some(outbox.record_failure(&entry, &err));
+                    }
+                    Err(err) => {
+                        outbox.finish(&entry);
+                        let failed = Failed {
+                            entry: &entry,
+                            error: err.to_string(),
+                        };
+                        if let Err(err) = app.emit_all("outbox://failed", failed) {
+                            eprintln!("failed to emit outbox://failed: {err}");
+                        }
+                    }
+                }
+                outbox.emit_changed(&app);
+                if let Some(delay) = retry_after {
+                    thread::sleep(delay);
+                }
+            })
+            .expect("failed to spawn outbox replay thread");
+    }
+
+    /// Drops the head entry and remembers its key.
+    fn finish(&self, entry: &OutboxEntry) {
+        let mut queue = self.queue.lock().unwrap();
+        queue
+            .pending
+            .retain(|e| e.idempotency_key != entry.idempotency_key);
+        if queue.applied.len() == MAX_APPLIED_KEYS {
+            queue.applied.pop_front();
+        }
+        queue.applied.push_back(entry.idempotency_key);
+        if let Err(err) = self.storage.save(DOCUMENT, &*queue) {
+            // the mutation is idempotent, so replaying it after a restart is fine
+            eprintln!("failed to save outbox: {err}");
+        }
+    }
+
+    /// Returns how long to wait before retrying.
+    fn record_failure(&self, entry: &OutboxEntry, err: &Error) -> Duration {
+        let mut queue = self.queue.lock().unwrap();
+        let Some(head) = queue
+            .pending
+            .iter_mut()
+            .find(|e| e.idempotency_key == entry.idempotency_key)
+        else {
+            return Duration::ZERO;
+        };
+        head.attempts += 1;
+        head.last_error = Some(err.to_string());
+        let attempts = head.attempts;
+        if let Err(err) = self.storage.save(DOCUMENT, &*queue) {
+            eprintln!("failed to save outbox: {err}");
+        }
+        Duration::from_millis(200)
+            .saturating_mul(2u32.saturating_pow(attempts.min(16)))
+            .min(MAX_RETRY_DELAY)
+    }
+
+    fn emit_changed(&self, app: &AppHandle) {
+        if let Err(err) = app.emit_all("outbox://changed", self.pending()) {
+            eprintln!("failed to emit outbox://changed: {err}");
+        }
+    }
+}
+
+#[tauri::command]
+pub fn enqueue_mutation(
+    app: AppHandle,
+    window: Window,
+    outbox: State<'_, Outbox>,
+    entry: OutboxEntry,
+) -> Result<Vec<OutboxEntry>> {
+    auth::require(&window, entry.mutation.capability())?;
+    entry.mutation.validate()?;
+    outbox.enqueue(entry)?;
+    outbox.emit_changed(&app);
+    Ok(outbox.pending())
+}
+
+#[tauri::command]
+pub fn list_outbox(window: Window, outbox: State<'_, Outbox>) -> Result<Vec<OutboxEntry>> {
+    auth::require(&window, Capability::TodoRead)?;
+    Ok(outbox.pending())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn outbox() -> (Outbox, Storage) {
+        let dir = env::temp_dir().join(format!("outbox-{}", Uuid::new_v4()));
+        let storage = Storage::open(dir).unwrap();
+        (Outbox::load(storage.clone()).unwrap(), storage)
+    }
+
+    fn entry(idempotency_key: Uuid) -> OutboxEntry {
+        OutboxEntry {
+            idempotency_key,
+            mutation: Mutation::DeleteTodo { id: Uuid::new_v4() },
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    fn keys(entries: &[OutboxEntry]) -> Vec<Uuid> {
+        entries.iter().map(|entry| entry.idempotency_key).collect()
+    }
+
+    #[test]
+    fn duplicate_idempotency_key_is_dropped() {
+        let (outbox, storage) = outbox();
+        let key = Uuid::new_v4();
+        outbox.enqueue(entry(key)).unwrap();
+        outbox.enqueue(entry(key)).unwrap();
+        assert_eq!(keys(&outbox.pending()), [key]);
+        assert_eq!(keys(&Outbox::load(storage).unwrap().pending()), [key]);
+    }
+
+    #[test]
+    fn applied_idempotency_key_is_dropped() {
+        let (outbox, _) = outbox();
+        let applied = entry(Uuid::new_v4());
+        outbox.enqueue(applied.clone()).unwrap();
+        outbox.finish(&applied);
+        outbox.enqueue(applied).unwrap();
+        assert!(outbox.pending().is_empty());
+    }
+
+    #[test]
+    fn failed_apply_keeps_the_entry_at_the_head() {
+        let (outbox, storage) = outbox();
+        let (first, second) = (entry(Uuid::new_v4()), entry(Uuid::new_v4()));
+        outbox.enqueue(first.clone()).unwrap();
+        outbox.enqueue(second.clone()).unwrap();
+        let delay = outbox.record_failure(&first, &Error::Busy("locked".into()));
+        assert!(delay > Duration::ZERO);
+        for pending in [outbox.pending(), Outbox::load(storage).unwrap().pending()] {
+            assert_eq!(keys(&pending), [first.idempotency_key, second.idempotency_key]);
+            assert_eq!(pending[0].attempts, 1);
+            assert_eq!(pending[0].last_error.as_deref(), Some("busy: locked"));
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use rrule::{RRule, RRuleError, Tz, Unvalidated};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// How a todo repeats once completed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    /// An iCalendar RRULE, like `FREQ=MONTHLY;BYMONTHDAY=1`, with or without
+    /// the `RRULE:` prefix.
+    Rule { rrule: String },
+}
+
+impl Recurrence {
+    /// Fails with [`Error::Invalid`] if the rule can't be parsed.
+    pub fn validate(&self) -> Result<()> {
+        self.next(Utc::now()).map(|_| ())
+    }
+
+    /// The occurrence after `due`, or `None` if the rule has run out.
+    ///
+    /// Days and weeks are counted in local time, so a todo due at 9:00 stays
+    /// at 9:00 across daylight saving changes.
+    pub fn next(&self, due: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+        self.next_in(due, &Tz::LOCAL)
+    }
+
+    /// [`Recurrence::next`], counting days and weeks in `tz`.
+    fn next_in(&self, due: DateTime<Utc>, tz: &Tz) -> Result<Option<DateTime<Utc>>> {
+        match self {
+            Recurrence::Daily => Ok(dates::add_days(due, 1, tz)),
+            Recurrence::Weekly => Ok(dates::add_days(due, 7, tz)),
+            Recurrence::Rule { rrule } => next_by_rule(rrule, due, tz),
+        }
+    }
+}
+
+fn next_by_rule(rule: &str, due: DateTime<Utc>, tz: &Tz) -> Result<Option<DateTime<Utc>>> {
+    let rule = rule.trim();
+    let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+    let invalid = |err: RRuleError| Error::Invalid(format!("recurrence rule `{rule}`: {err}"));
+    // started in local time, so BYDAY and BYHOUR mean local days and hours
+    let set = rule
+        .parse::<RRule<Unvalidated>>()
+        .map_err(invalid)?
+        .build(due.with_timezone(tz))
+        .map_err(invalid)?;
+    // `after` is inclusive, and the start itself is an occurrence
+    let after = (due + chrono::Duration::seconds(1)).with_timezone(tz);
+    let next = set.after(after).all(1).dates.into_iter().next();
+    Ok(next.map(|next| next.with_timezone(&Utc)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BERLIN: Tz = Tz::Europe__Berlin;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn rule(rrule: &str) -> Recurrence {
+        Recurrence::Rule {
+            rrule: rrule.to_owned(),
+        }
+    }
+
+    #[test]
+    fn daily_keeps_the_local_time_across_daylight_saving() {
+        // 9:00 CET, then 9:00 CEST after a 23 hour day
+        let next = Recurrence::Daily.next_in(utc("2024-03-30T08:00:00Z"), &BERLIN);
+        assert_eq!(next.unwrap(), Some(utc("2024-03-31T07:00:00Z")));
+    }
+
+    #[test]
+    fn weekly_keeps_the_local_time_across_daylight_saving() {
+        // 9:00 CEST, then 9:00 CET a week later
+        let next = Recurrence::Weekly.next_in(utc("2024-10-21T07:00:00Z"), &BERLIN);
+        assert_eq!(next.unwrap(), Some(utc("2024-10-28T08:00:00Z")));
+    }
+
+    #[test]
+    fn rule_keeps_the_local_time_across_daylight_saving() {
+        let next = rule("FREQ=DAILY").next_in(utc("2024-03-30T08:00:00Z"), &BERLIN);
+        assert_eq!(next.unwrap(), Some(utc("2024-03-31T07:00:00Z")));
+    }
+
+    #[test]
+    fn rule_counts_days_in_local_time() {
+        // from Friday 9:00 CEST to Monday 9:00 CET
+        let next = rule("RRULE:FREQ=WEEKLY;BYDAY=MO").next_in(utc("2024-10-25T07:00:00Z"), &BERLIN);
+        assert_eq!(next.unwrap(), Some(utc("2024-10-28T08:00:00Z")));
+    }
+
+    #[test]
+    fn rule_that_has_run_out() {
+        let next = rule("FREQ=DAILY;COUNT=1").next_in(utc("2024-03-30T08:00:00Z"), &BERLIN);
+        assert_eq!(next.unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_rule() {
+        assert!(matches!(rule("FREQ=SOMETIMES").validate(), Err(Error::Invalid(_))));
+    }
+}
@@ -0,0 +1,302 @@
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::changes;
+use crate::error::{Error, Result};
+use crate::storage::Storage;
+use crate::todos::{Todo, Todos};
+
+const DOCUMENT: &str = "reminders";
+/// Longest the scheduler sleeps without looking at the clock again, so it
+/// notices the clock being changed or the machine waking from sleep.
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+/// Reminders missed by more than this while the app wasn't running are
+/// skipped rather than fired late.
+const MISSED_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+const MAX_SNOOZE_MINUTES: u32 = 7 * 24 * 60;
+
+/// Identifies a reminder. Changing a todo's due date or reminder makes a new
+/// one, so it fires again even if the old one was dismissed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderKey {
+    pub todo_id: Uuid,
+    /// When it was set to fire, before any snoozing.
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ReminderState {
+    /// Shown, but not snoozed or dismissed yet.
+    Fired {
+        /// Missing from schedules saved before it was kept.
+        #[serde(default)]
+        at: Option<DateTime<Utc>>,
+    },
+    Snoozed { until: DateTime<Utc> },
+    Dismissed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    key: ReminderKey,
+    state: ReminderState,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Schedule {
+    records: Vec<Record>,
+    /// Set when the scheduler should look at the todos again before its
+    /// sleep is up.
+    #[serde(skip)]
+    rescheduled: bool,
+}
+
+impl Schedule {
+    fn state(&self, key: ReminderKey) -> Option<ReminderState> {
+        self.records
+            .iter()
+            .find(|record| record.key == key)
+            .map(|record| record.state)
+    }
+
+    fn set(&mut self, key: ReminderKey, state: ReminderState) {
+        match self.records.iter_mut().find(|record| record.key == key) {
+            Some(record) => record.state = state,
+            None => self.records.push(Record { key, state }),
+        }
+    }
+
+    /// Reminders that haven't fired yet or were snoozed, and those that fired
+    /// but haven't been snoozed or dismissed, soonest first.
+    fn upcoming(&self, todos: &[Todo], now: DateTime<Utc>) -> Vec<Reminder> {
+        let mut upcoming = todos
+            .iter()
+            .filter_map(Reminder::of)
+            .filter_map(|mut reminder| {
+                reminder.fires_at = match self.state(reminder.key) {
+                    None => reminder.key.at,
+                    Some(ReminderState::Snoozed { until }) => until,
+                    // kept until handled, however long ago it fired
+                    Some(ReminderState::Fired { at }) => {
+                        reminder.fired = true;
+                        reminder.fires_at = at.unwrap_or(reminder.key.at);
+                        return Some(reminder);
+                    }
+                    Some(ReminderState::Dismissed) => return None,
+                };
+                // negative for reminders still to come, which don't convert
+                let late = (now - reminder.fires_at).to_std();
+                late.ok().is_none_or(|late| late < MISSED_GRACE).then_some(reminder)
+            })
+            .collect::<Vec<_>>();
+        upcoming.sort_by_key(|reminder| reminder.fires_at);
+        upcoming
+    }
+
+    /// Forgets reminders whose todo is gone, done, or due at another time.
+    fn prune(&mut self, todos: &[Todo]) {
+        let current = todos.iter().filter_map(Reminder::of).collect::<Vec<_>>();
+        self.records
+            .retain(|record| current.iter().any(|reminder| reminder.key == record.key));
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Reminder {
+    pub key: ReminderKey,
+    /// `key.at`, or later if snoozed.
+    pub fires_at: DateTime<Utc>,
+    pub text: String,
+    pub due: DateTime<Utc>,
+    /// Fired, and waiting to be snoozed or dismissed.
+    pub fired: bool,
+}
+
+impl Reminder {
+    /// The reminder for a todo that isn't done and has one set.
+    fn of(todo: &Todo) -> Option<Self> {
+        let due = todo.details.due.filter(|_| !todo.done)?;
+        let before = chrono::Duration::minutes(todo.details.remind_before?.into());
+        let at = due - before;
+        Some(Self {
+            key: ReminderKey {
+                todo_id: todo.id,
+                at,
+            },
+            fires_at: at,
+            text: todo.text.clone(),
+            due,
+            fired: false,
+        })
+    }
+}
+
+/// Fires reminders for todos as they come due, emitting each as
+/// `reminder://due`.
+///
+/// Which reminders have fired, been snoozed or been dismissed is saved, so a
+/// restart neither repeats them nor loses snoozes; reminders that came due
+/// while the app wasn't running fire once it starts. A fired reminder stays
+/// in `list_reminders` until it's snoozed or dismissed, so a window that
+/// wasn't listening when it fired, say at startup, still shows it. Snoozing
+/// or dismissing emits `reminder://handled` with the key, so every window can
+/// drop it.
+pub struct Reminders {
+    storage: Storage,
+    schedule: Mutex<Schedule>,
+    wake: Condvar,
+}
+
+impl Reminders {
+    pub fn load(storage: Storage) -> Result<Self> {
+        let schedule = storage.load(DOCUMENT)?.unwrap_or_default();
+        Ok(Self {
+            storage,
+            schedule: Mutex::new(schedule),
+            wake: Condvar::new(),
+        })
+    }
+
+    pub fn upcoming(&self, todos: &[Todo]) -> Vec<Reminder> {
+        self.schedule.lock().unwrap().upcoming(todos, Utc::now())
+    }
+
+    /// Has the scheduler look at the todos again, after they changed.
+    pub fn reschedule(&self) {
+        self.schedule.lock().unwrap().rescheduled = true;
+        self.wake.notify_one();
+    }
+
+    fn set(&self, key: ReminderKey, state: ReminderState) -> Result<()> {
+        let mut schedule = self.schedule.lock().unwrap();
+        schedule.set(key, state);
+        schedule.rescheduled = true;
+        self.storage.save(DOCUMENT, &*schedule)?;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn snooze(&self, key: ReminderKey, until: DateTime<Utc>) -> Result<()> {
+        self.set(key, ReminderState::Snoozed { until })
+    }
+
+    pub fn dismiss(&self, key: ReminderKey) -> Result<()> {
+        self.set(key, ReminderState::Dismissed)
+    }
+
+    /// Marks the reminders due by `now` as fired and returns them, with when
+    /// the next one is due.
+    fn take_due(
+        &self,
+        todos: &[Todo],
+        now: DateTime<Utc>,
+    ) -> (Vec<Reminder>, Option<DateTime<Utc>>) {
+        let mut schedule = self.schedule.lock().unwrap();
+        let upcoming = schedule.upcoming(todos, now);
+        let (mut due, later): (Vec<_>, Vec<_>) = upcoming
+            .into_iter()
+            .filter(|reminder| !reminder.fired)
+            .partition(|reminder| reminder.fires_at <= now);
+        let before = schedule.records.len();
+        schedule.prune(todos);
+        for reminder in &mut due {
+            reminder.fired = true;
+            schedule.set(reminder.key, ReminderState::Fired { at: Some(now) });
+        }
+        if !due.is_empty() || schedule.records.len() != before {
+            if let Err(err) = self.storage.save(DOCUMENT, &*schedule) {
+                // at worst the reminders fire again after a restart
+                eprintln!("failed to save reminders: {err}");
+            }
+        }
+        (due, later.first().map(|reminder| reminder.fires_at))
+    }
+
+    /// Fires reminders on a background thread.
+    ///
+    /// The thread wakes when the next reminder is due, when the todos or
+    /// reminders change, and at least every [`MAX_SLEEP`]; times are always
+    /// compared against the wall clock, so a changed clock is caught up with.
+    pub fn start(app: AppHandle) {
+        thread::Builder::new()
+            .name("reminders".into())
+            .spawn(move || loop {
+                let reminders = app.state::<Reminders>();
+                let now = Utc::now();
+                let (due, next) = reminders.take_due(&app.state::<Todos>().list(), now);
+                for reminder in &due {
+                    if let Err(err) = app.emit_all("reminder://due", reminder) {
+                        eprintln!("failed to emit reminder://due: {err}");
+                    }
+                }
+                if !due.is_empty() {
+                    changes::emit(&app, &["list_reminders"]);
+                }
+                let sleep = next
+                    .map_or(MAX_SLEEP, |next| (next - now).to_std().unwrap_or(Duration::ZERO))
+                    .min(MAX_SLEEP);
+                let schedule = reminders.schedule.lock().unwrap();
+                let (mut schedule, _) = reminders
+                    .wake
+                    .wait_timeout_while(schedule, sleep, |schedule| !schedule.rescheduled)
+                    .unwrap();
+                schedule.rescheduled = false;
+            })
+            .expect("failed to spawn reminder thread");
+    }
+}
+
+#[tauri::command]
+pub fn list_reminders(
+    window: Window,
+    reminders: State<'_, Reminders>,
+    todos: State<'_, Todos>,
+) -> Result<Vec<Reminder>> {
+    auth::require(&window, Capability::TodoRead)?;
+    Ok(reminders.upcoming(&todos.list()))
+}
+
+#[tauri::command]
+pub fn snooze_reminder(
+    window: Window,
+    reminders: State<'_, Reminders>,
+    key: ReminderKey,
+    minutes: u32,
+) -> Result<()> {
+    auth::require(&window, Capability::TodoWrite)?;
+    if !(1..=MAX_SNOOZE_MINUTES).contains(&minutes) {
+        return Err(Error::Invalid(format!(
+            "snooze of {minutes} minutes; it must be 1 to {MAX_SNOOZE_MINUTES}"
+        )));
+    }
+    reminders.snooze(key, Utc::now() + chrono::Duration::minutes(minutes.into()))?;
+    handled(&window, key);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn dismiss_reminder(
+    window: Window,
+    reminders: State<'_, Reminders>,
+    key: ReminderKey,
+) -> Result<()> {
+    auth::require(&window, Capability::TodoWrite)?;
+    reminders.dismiss(key)?;
+    handled(&window, key);
+    Ok(())
+}
+
+fn handled(window: &Window, key: ReminderKey) {
+    if let Err(err) = window.emit_all("reminder://handled", key) {
+        eprintln!("failed to emit reminder://handled: {err}");
+    }
+    changes::emit(window, &["list_reminders"]);
+}
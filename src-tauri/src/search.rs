@@ -0,0 +1,275 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tantivy::collector::TopDocs;
+use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, STORED, STRING, TEXT};
+use tantivy::{Document, Index, IndexReader, IndexWriter, ReloadPolicy, SnippetGenerator, Term};
+use tauri::{State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::error::Result;
+
+/// Memory the index writer may use before flushing; tantivy's minimum.
+const WRITER_MEMORY: usize = 15_000_000;
+const MAX_LIMIT: usize = 100;
+/// Longest snippet shown for a hit, in characters.
+const SNIPPET_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Todo,
+    Note,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Todo => "todo",
+            Kind::Note => "note",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "todo" => Some(Kind::Todo),
+            "note" => Some(Kind::Note),
+            _ => None,
+        }
+    }
+
+    fn capability(self) -> Capability {
+        match self {
+            Kind::Todo => Capability::TodoRead,
+            Kind::Note => Capability::NoteRead,
+        }
+    }
+}
+
+/// Something to index, replacing whatever was indexed under its id.
+pub struct Entry<'a> {
+    pub id: Uuid,
+    pub kind: Kind,
+    pub title: &'a str,
+    pub body: &'a str,
+}
+
+/// Text with the byte ranges that matched the query.
+#[derive(Debug, Clone, Serialize)]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hit {
+    pub id: Uuid,
+    pub kind: Kind,
+    pub score: f32,
+    pub title: Snippet,
+    /// Empty when only the title matched.
+    pub body: Snippet,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Only these kinds; all of them if empty.
+    pub kinds: Vec<Kind>,
+}
+
+struct Fields {
+    id: Field,
+    kind: Field,
+    title: Field,
+    body: Field,
+}
+
+struct Inner {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: Fields,
+}
+
+/// A full-text index of todos and notes, kept up to date by their stores.
+///
+/// It lives in memory and the stores fill it as they load, so it can never
+/// disagree with what's saved. Failing to index is logged rather than
+/// failing the change being indexed.
+#[derive(Clone)]
+pub struct SearchIndex(Arc<Inner>);
+
+impl SearchIndex {
+    pub fn new() -> Result<Self> {
+        let mut schema = Schema::builder();
+        let fields = Fields {
+            id: schema.add_text_field("id", STRING | STORED),
+            kind: schema.add_text_field("kind", STRING | STORED),
+            title: schema.add_text_field("title", TEXT | STORED),
+            body: schema.add_text_field("body", TEXT | STORED),
+        };
+        let index = Index::create_in_ram(schema.build());
+        let writer = index.writer(WRITER_MEMORY)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self(Arc::new(Inner {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            fields,
+        })))
+    }
+
+    fn id_term(&self, id: Uuid) -> Term {
+        Term::from_field_text(self.0.fields.id, &id.to_string())
+    }
+
+    /// Indexes every entry with a single commit.
+    pub fn index_all<'a>(&self, entries: impl IntoIterator<Item = Entry<'a>>) {
+        let fields = &self.0.fields;
+        let result = self.commit(|writer| {
+            for entry in entries {
+                let mut document = Document::new();
+                document.add_text(fields.id, entry.id.to_string());
+                document.add_text(fields.kind, entry.kind.as_str());
+                document.add_text(fields.title, entry.title);
+                document.add_text(fields.body, entry.body);
+                writer.delete_term(self.id_term(entry.id));
+                writer.add_document(document)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            eprintln!("failed to update the search index: {err}");
+        }
+    }
+
+    pub fn index(&self, entry: Entry) {
+        self.index_all([entry]);
+    }
+
+    pub fn remove(&self, id: Uuid) {
+        let result = self.commit(|writer| {
+            writer.delete_term(self.id_term(id));
+            Ok(())
+        });
+        if let Err(err) = result {
+            eprintln!("failed to update the search index: {err}");
+        }
+    }
+
+    fn commit(&self, change: impl FnOnce(&mut IndexWriter) -> Result<()>) -> Result<()> {
+        let mut writer = self.0.writer.lock().unwrap();
+        change(&mut writer)?;
+        writer.commit()?;
+        self.0.reader.reload()?;
+        Ok(())
+    }
+
+    /// The best matches for `query` among `kinds`, best first.
+    pub fn search(&self, query: &str, kinds: &[Kind], limit: usize) -> Result<Vec<Hit>> {
+        let Inner {
+            index,
+            reader,
+            fields,
+            ..
+        } = &*self.0;
+        if query.trim().is_empty() || kinds.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut parser = QueryParser::for_index(index, vec![fields.title, fields.body]);
+        parser.set_field_boost(fields.title, 2.0);
+        // typing half a query shouldn't be an error
+        let (text_query, _) = parser.parse_query_lenient(query);
+        let kind_query = BooleanQuery::new(
+            kinds
+                .iter()
+                .map(|kind| {
+                    let term = Term::from_field_text(fields.kind, kind.as_str());
+                    let query: Box<dyn Query> =
+                        Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+                    (Occur::Should, query)
+                })
+                .collect(),
+        );
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, text_query.box_clone()),
+            (Occur::Must, Box::new(kind_query)),
+        ]);
+
+        let searcher = reader.searcher();
+        let top = searcher.search(&query, &TopDocs::with_limit(limit.clamp(1, MAX_LIMIT)))?;
+        let mut titles = SnippetGenerator::create(&searcher, &*text_query, fields.title)?;
+        titles.set_max_num_chars(usize::MAX);
+        let mut bodies = SnippetGenerator::create(&searcher, &*text_query, fields.body)?;
+        bodies.set_max_num_chars(SNIPPET_CHARS);
+
+        let mut hits = Vec::with_capacity(top.len());
+        for (score, address) in top {
+            let document: Document = searcher.doc(address)?;
+            let text = |field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_text())
+                    .unwrap_or_default()
+            };
+            let id = text(fields.id).parse::<Uuid>();
+            let (Ok(id), Some(kind)) = (id, Kind::parse(text(fields.kind))) else {
+                continue;
+            };
+            let mut title = snippet(&titles, &document);
+            if title.text.is_empty() {
+                // snippets only cover what matched
+                title.text = text(fields.title).to_owned();
+            }
+            hits.push(Hit {
+                id,
+                kind,
+                score,
+                title,
+                body: snippet(&bodies, &document),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+fn snippet(generator: &SnippetGenerator, document: &Document) -> Snippet {
+    let snippet = generator.snippet_from_doc(document);
+    Snippet {
+        text: snippet.fragment().to_owned(),
+        highlights: snippet
+            .highlighted()
+            .iter()
+            .map(|range| range.bounds())
+            .collect(),
+    }
+}
+
+/// Searches todos and notes, leaving out the kinds the calling window can't
+/// read.
+#[tauri::command]
+pub fn search(
+    window: Window,
+    index: State<'_, SearchIndex>,
+    query: String,
+    filters: SearchFilters,
+    limit: usize,
+) -> Result<Vec<Hit>> {
+    auth::require(&window, Capability::Search)?;
+    let kinds = if filters.kinds.is_empty() {
+        vec![Kind::Todo, Kind::Note]
+    } else {
+        filters.kinds
+    };
+    let readable = kinds
+        .into_iter()
+        .filter(|kind| auth::require(&window, kind.capability()).is_ok())
+        .collect::<Vec<_>>();
+    index.search(&query, &readable, limit)
+}
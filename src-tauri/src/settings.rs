@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+
+use crate::auth::{self, Capability};
+use crate::changes;
+use crate::error::Result;
+use crate::storage::Storage;
+use crate::todos::Status;
+
+const DOCUMENT: &str = "settings";
+
+/// User preferences, shared by all windows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Shortcut overrides by command id, like `"Ctrl+Shift+K"`. `None`
+    /// unbinds the command.
+    pub shortcuts: BTreeMap<String, Option<String>>,
+    /// Closing the main window hides it to the tray instead.
+    pub close_to_tray: bool,
+    /// The board's columns, left to right. Empty for one column per status.
+    pub kanban_columns: Vec<KanbanColumn>,
+}
+
+/// Which todos a board column holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum ColumnFilter {
+    Status { status: Status },
+    /// Todos with the tag, done or not.
+    Tag { tag: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KanbanColumn {
+    pub title: String,
+    pub filter: ColumnFilter,
+    /// How many todos the column may hold; moving more in is refused.
+    #[serde(default)]
+    pub wip_limit: Option<u32>,
+}
+
+/// Settings, saved to storage on every change.
+pub struct SettingsStore {
+    storage: Storage,
+    settings: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    pub fn load(storage: Storage) -> Result<Self> {
+        let settings = storage.load(DOCUMENT)?.unwrap_or_default();
+        Ok(Self {
+            storage,
+            settings: Mutex::new(settings),
+        })
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, settings: Settings) -> Result<()> {
+        let mut current = self.settings.lock().unwrap();
+        self.storage.save(DOCUMENT, &settings)?;
+        *current = settings;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(window: Window, store: State<'_, SettingsStore>) -> Result<Settings> {
+    auth::require(&window, Capability::SettingsRead)?;
+    Ok(store.get())
+}
+
+#[tauri::command]
+pub fn update_settings(
+    window: Window,
+    store: State<'_, SettingsStore>,
+    settings: Settings,
+) -> Result<()> {
+    auth::require(&window, Capability::SettingsWrite)?;
+    store.set(settings)?;
+    changes::emit(&window, &["get_settings"]);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// JSON documents and plain files in the app data directory.
+///
+/// Writes go to a temporary file that is renamed over the old one, so a crash
+/// mid-write leaves the previous version intact.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    dir: PathBuf,
+}
+
+impl Storage {
+    pub fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Storage in a subdirectory, e.g. for one file per note.
+    pub fn subdir(&self, name: &str) -> io::Result<Self> {
+        Self::open(self.dir.join(name))
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.json"))
+    }
+
+    /// `None` if the document was never saved.
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>> {
+        match fs::read(self.path(name)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save<T: Serialize + ?Sized>(&self, name: &str, value: &T) -> Result<()> {
+        write_atomic(&self.path(name), &serde_json::to_vec_pretty(value)?)
+    }
+
+    /// Removing a document that was never saved is fine.
+    pub fn remove(&self, name: &str) -> Result<()> {
+        remove_if_exists(&self.path(name))
+    }
+
+    /// A plain file by its full name, `None` if it was never written.
+    pub fn read_file(&self, file: &str) -> Result<Option<String>> {
+        match fs::read_to_string(self.dir.join(file)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn write_file(&self, file: &str, contents: &str) -> Result<()> {
+        write_atomic(&self.dir.join(file), contents.as_bytes())
+    }
+
+    /// Removing a file that doesn't exist is fine.
+    pub fn remove_file(&self, file: &str) -> Result<()> {
+        remove_if_exists(&self.dir.join(file))
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
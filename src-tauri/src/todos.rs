@@ -0,0 +1,314 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use uuid::Uuid;
+
+use crate::auth::{self, Capability};
+use crate::error::{Error, Result};
+use crate::recurrence::Recurrence;
+use crate::search::{Entry, Kind, SearchIndex};
+use crate::storage::Storage;
+
+const DOCUMENT: &str = "todos";
+/// Queries that may be affected by any change to a todo.
+pub const CHANGED_QUERIES: &[&str] = &["list_todos", "list_reminders", "search"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Where a todo is on the board; `Done` is the same as [`Todo::done`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Open,
+    InProgress,
+    Done,
+}
+
+/// Everything about a todo besides its text, all optional.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TodoDetails {
+    pub due: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub notes: String,
+    /// Completing a recurring todo adds its next occurrence.
+    pub recurrence: Option<Recurrence>,
+    /// Minutes before `due` to remind; no reminder if `None`.
+    pub remind_before: Option<u32>,
+    /// Being worked on. Completing the todo clears it.
+    pub in_progress: bool,
+}
+
+impl TodoDetails {
+    pub fn validate(&self) -> Result<()> {
+        match &self.recurrence {
+            Some(recurrence) => recurrence.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub id: Uuid,
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+    #[serde(flatten)]
+    pub details: TodoDetails,
+}
+
+impl Todo {
+    fn entry(&self) -> Entry {
+        Entry {
+            id: self.id,
+            kind: Kind::Todo,
+            title: &self.text,
+            body: &self.details.notes,
+        }
+    }
+}
+
+/// Todos, saved to storage and indexed for search after every change.
+pub struct Todos {
+    storage: Storage,
+    search: SearchIndex,
+    todos: Mutex<Vec<Todo>>,
+}
+
+impl Todos {
+    pub fn load(storage: Storage, search: SearchIndex) -> Result<Self> {
+        let todos: Vec<Todo> = storage.load(DOCUMENT)?.unwrap_or_default();
+        search.index_all(todos.iter().map(Todo::entry));
+        Ok(Self {
+            storage,
+            search,
+            todos: Mutex::new(todos),
+        })
+    }
+
+    pub fn list(&self) -> Vec<Todo> {
+        self.todos.lock().unwrap().clone()
+    }
+
+    /// Adds a todo unless one with `id` already exists, so replaying the same
+    /// addition is harmless.
+    pub fn insert(&self, id: Uuid, text: String, details: TodoDetails) -> Result<()> {
+        let mut todos = self.todos.lock().unwrap();
+        if todos.iter().any(|todo| todo.id == id) {
+            return Ok(());
+        }
+        let todo = Todo {
+            id,
+            text,
+            done: false,
+            details,
+        };
+        // kept and indexed only once saved, so a failed save changes nothing
+        let saved = todos.iter().chain([&todo]).collect::<Vec<_>>();
+        self.storage.save(DOCUMENT, &saved)?;
+        self.search.index(todo.entry());
+        todos.push(todo);
+        Ok(())
+    }
+
+    /// Applies `change` to a copy of the list, given the todo's index, and
+    /// keeps and indexes the copy once it's saved.
+    fn modify(
+        &self,
+        id: Uuid,
+        change: impl FnOnce(&mut Vec<Todo>, usize) -> Result<()>,
+    ) -> Result<()> {
+        let mut todos = self.todos.lock().unwrap();
+        let Some(index) = todos.iter().position(|todo| todo.id == id) else {
+            return Err(Error::NotFound(format!("todo {id}")));
+        };
+        let mut changed = todos.clone();
+        change(&mut changed, index)?;
+        self.storage.save(DOCUMENT, &changed)?;
+        self.reindex(&todos, &changed);
+        *todos = changed;
+        Ok(())
+    }
+
+    /// Indexes the todos that were added or whose text changed, and drops
+    /// the ones that are gone.
+    fn reindex(&self, before: &[Todo], after: &[Todo]) {
+        for todo in before {
+            if after.iter().all(|other| other.id != todo.id) {
+                self.search.remove(todo.id);
+            }
+        }
+        for todo in after {
+            let unchanged = before.iter().any(|other| {
+                other.id == todo.id
+                    && other.text == todo.text
+                    && other.details.notes == todo.details.notes
+            });
+            if !unchanged {
+                self.search.index(todo.entry());
+            }
+        }
+    }
+
+    pub fn update(&self, id: Uuid, text: String, details: TodoDetails) -> Result<()> {
+        self.modify(id, |todos, index| {
+            todos[index].text = text;
+            todos[index].details = details;
+            Ok(())
+        })
+    }
+
+    /// Marks the todo done and, if it recurs, adds its next occurrence as
+    /// `next_id`. Completing it again changes nothing, so replays are
+    /// harmless.
+    pub fn complete(&self, id: Uuid, next_id: Uuid) -> Result<()> {
+        self.modify(id, |todos, index| {
+            let todo = &todos[index];
+            if todo.done {
+                return Ok(());
+            }
+            // worked out first, so a rule that fails leaves the todo undone
+            let next = match &todo.details.recurrence {
+                Some(recurrence) => recurrence.next(todo.details.due.unwrap_or_else(Utc::now))?,
+                None => None,
+            };
+            let next = next.map(|due| Todo {
+                id: next_id,
+                text: todo.text.clone(),
+                done: false,
+                details: TodoDetails {
+                    due: Some(due),
+                    in_progress: false,
+                    ..todo.details.clone()
+                },
+            });
+            let todo = &mut todos[index];
+            todo.done = true;
+            todo.details.in_progress = false;
+            if let Some(next) = next.filter(|_| todos.iter().all(|todo| todo.id != next_id)) {
+                todos.push(next);
+            }
+            Ok(())
+        })
+    }
+
+    /// Undoes [`Todos::complete`], removing the occurrence it added.
+    pub fn reopen(&self, id: Uuid, next_id: Uuid) -> Result<()> {
+        self.modify(id, |todos, index| {
+            todos[index].done = false;
+            todos.retain(|todo| todo.id != next_id);
+            Ok(())
+        })
+    }
+
+    /// Moves the todo to another column of the board, completing or
+    /// reopening it as needed; `next_id` is as for [`Todos::complete`].
+    pub fn set_status(&self, id: Uuid, status: Status, next_id: Uuid) -> Result<()> {
+        if status == Status::Done {
+            return self.complete(id, next_id);
+        }
+        // reopened and moved with a single save
+        self.modify(id, |todos, index| {
+            todos[index].done = false;
+            todos[index].details.in_progress = status == Status::InProgress;
+            todos.retain(|todo| todo.id != next_id);
+            Ok(())
+        })
+    }
+
+    /// Returns whether a todo was removed.
+    pub fn remove(&self, id: Uuid) -> Result<bool> {
+        let mut todos = self.todos.lock().unwrap();
+        let kept = todos
+            .iter()
+            .filter(|todo| todo.id != id)
+            .cloned()
+            .collect::<Vec<_>>();
+        if kept.len() == todos.len() {
+            return Ok(false);
+        }
+        self.storage.save(DOCUMENT, &kept)?;
+        *todos = kept;
+        self.search.remove(id);
+        Ok(true)
+    }
+}
+
+#[tauri::command]
+pub fn list_todos(window: Window, todos: State<'_, Todos>) -> Result<Vec<Todo>> {
+    auth::require(&window, Capability::TodoRead)?;
+    Ok(todos.list())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::{env, fs};
+
+    use super::*;
+
+    /// Todos in a fresh directory, which is returned too.
+    fn todos() -> (Todos, PathBuf) {
+        let dir = env::temp_dir().join(format!("todos-{}", Uuid::new_v4()));
+        let storage = Storage::open(dir.clone()).unwrap();
+        let todos = Todos::load(storage, SearchIndex::new().unwrap()).unwrap();
+        (todos, dir)
+    }
+
+    /// Makes every later save fail, leaving the saved document as it is.
+    fn break_saves(dir: &Path) {
+        fs::create_dir(dir.join(format!("{DOCUMENT}.json.tmp"))).unwrap();
+    }
+
+    fn saved(dir: &Path) -> String {
+        fs::read_to_string(dir.join(format!("{DOCUMENT}.json"))).unwrap()
+    }
+
+    fn texts(todos: &Todos) -> Vec<String> {
+        todos.list().into_iter().map(|todo| todo.text).collect()
+    }
+
+    #[test]
+    fn failed_update_leaves_memory_and_disk_unchanged() {
+        let (todos, dir) = todos();
+        let id = Uuid::new_v4();
+        todos.insert(id, "milk".into(), TodoDetails::default()).unwrap();
+        let before = saved(&dir);
+        break_saves(&dir);
+        let details = TodoDetails::default();
+        assert!(todos.update(id, "oat milk".into(), details).is_err());
+        assert_eq!(texts(&todos), ["milk"]);
+        assert_eq!(saved(&dir), before);
+    }
+
+    #[test]
+    fn failed_remove_leaves_memory_and_disk_unchanged() {
+        let (todos, dir) = todos();
+        let id = Uuid::new_v4();
+        todos.insert(id, "milk".into(), TodoDetails::default()).unwrap();
+        let before = saved(&dir);
+        break_saves(&dir);
+        assert!(todos.remove(id).is_err());
+        assert_eq!(texts(&todos), ["milk"]);
+        assert_eq!(saved(&dir), before);
+    }
+
+    #[test]
+    fn failed_insert_leaves_memory_unchanged() {
+        let (todos, dir) = todos();
+        break_saves(&dir);
+        assert!(todos.insert(Uuid::new_v4(), "milk".into(), TodoDetails::default()).is_err());
+        assert!(todos.list().is_empty());
+    }
+}
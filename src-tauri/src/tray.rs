@@ -0,0 +1,109 @@
+use tauri::{
+    AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem, Window,
+};
+
+use crate::menu::MenuAction;
+use crate::settings::SettingsStore;
+use crate::unsaved;
+
+/// Label of the window from tauri.conf.json.
+const MAIN_WINDOW: &str = "main";
+
+fn jobs_title(pending: usize) -> String {
+    match pending {
+        0 => "No jobs running".to_owned(),
+        1 => "1 job pending".to_owned(),
+        n => format!("{n} jobs pending"),
+    }
+}
+
+pub fn build() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("toggle", "Hide Window"))
+        .add_item(CustomMenuItem::new("quick_add", "Quick Add Todo..."))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("jobs", jobs_title(0)).disabled())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("quit", "Quit"));
+    SystemTray::new()
+        .with_menu(menu)
+        .with_tooltip("tauri-leptos")
+}
+
+pub fn handle_event(app: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } => show_main(app),
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            "toggle" => match app.get_window(MAIN_WINDOW) {
+                Some(window) if window.is_visible().unwrap_or(false) => hide(&window),
+                _ => show_main(app),
+            },
+            "quick_add" => {
+                show_main(app);
+                // handled like File > New Todo
+                if let Some(window) = app.get_window(MAIN_WINDOW) {
+                    if let Err(err) = window.emit("menu://action", MenuAction::AddTodo) {
+                        eprintln!("failed to emit menu://action: {err}");
+                    }
+                }
+            }
+            "quit" => unsaved::quit(app),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+fn set_toggle_title(app: &AppHandle, visible: bool) {
+    let title = if visible { "Hide Window" } else { "Show Window" };
+    if let Some(item) = app.tray_handle().try_get_item("toggle") {
+        if let Err(err) = item.set_title(title) {
+            eprintln!("failed to update the tray menu: {err}");
+        }
+    }
+}
+
+fn show_main(app: &AppHandle) {
+    let Some(window) = app.get_window(MAIN_WINDOW) else {
+        return;
+    };
+    let shown = window
+        .show()
+        .and_then(|()| window.unminimize())
+        .and_then(|()| window.set_focus());
+    if let Err(err) = shown {
+        eprintln!("failed to show the main window: {err}");
+    }
+    set_toggle_title(app, true);
+}
+
+fn hide(window: &Window) {
+    if let Err(err) = window.hide() {
+        eprintln!("failed to hide the main window: {err}");
+    }
+    set_toggle_title(&window.app_handle(), false);
+}
+
+/// Hides the main window instead of closing it when the close-to-tray
+/// setting is on. Returns whether the close should be prevented.
+pub fn close_to_tray(window: &Window) -> bool {
+    if window.label() != MAIN_WINDOW || !window.state::<SettingsStore>().get().close_to_tray {
+        return false;
+    }
+    hide(window);
+    true
+}
+
+/// Shows how many jobs are queued or running.
+pub fn set_job_count(app: &AppHandle, pending: usize) {
+    let tray = app.tray_handle();
+    let title = jobs_title(pending);
+    let updated = tray
+        .try_get_item("jobs")
+        .map_or(Ok(()), |item| item.set_title(&title))
+        .and_then(|()| tray.set_tooltip(&format!("tauri-leptos: {title}")));
+    if let Err(err) = updated {
+        eprintln!("failed to update the tray: {err}");
+    }
+}
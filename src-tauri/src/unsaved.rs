@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, State, Window};
+
+use crate::error::{Error, Result};
+
+/// Put in front of the title of a window with unsaved changes.
+const MARKER: &str = "• ";
+
+/// Labels of the windows whose UI has unsaved changes.
+///
+/// Closing such a window is vetoed and the UI is asked instead, with a
+/// `window://close-requested` event; it calls [`close_window`] once the user
+/// agrees to discard the changes. Quitting asks each such window in turn the
+/// same way, with `window://quit-requested` and [`quit_app`].
+#[derive(Default)]
+pub struct Unsaved {
+    dirty: Mutex<HashSet<String>>,
+    /// The window last asked whether to quit.
+    asked_to_quit: Mutex<Option<String>>,
+}
+
+impl Unsaved {
+    pub fn forget(&self, label: &str) {
+        self.dirty.lock().unwrap().remove(label);
+    }
+
+    fn is_dirty(&self, label: &str) -> bool {
+        self.dirty.lock().unwrap().contains(label)
+    }
+
+    fn any_dirty(&self) -> Option<String> {
+        self.dirty.lock().unwrap().iter().next().cloned()
+    }
+}
+
+/// Asks the UI whether to close `window` if it has unsaved changes. Returns
+/// whether the close should be prevented.
+pub fn confirm_close(window: &Window) -> bool {
+    if !window.state::<Unsaved>().is_dirty(window.label()) {
+        return false;
+    }
+    if let Err(err) = window.emit("window://close-requested", ()) {
+        // better to close than to leave a window that won't
+        eprintln!("failed to emit window://close-requested: {err}");
+        return false;
+    }
+    true
+}
+
+/// Quits the app, unless a window with unsaved changes is asked first; it
+/// calls [`quit_app`] to go on quitting once the user agrees to discard them.
+/// Every quit, from the menu or the tray, goes through here.
+pub fn quit(app: &AppHandle) {
+    let unsaved = app.state::<Unsaved>();
+    while let Some(label) = unsaved.any_dirty() {
+        let Some(window) = app.get_window(&label) else {
+            unsaved.forget(&label);
+            continue;
+        };
+        let shown = window
+            .show()
+            .and_then(|()| window.unminimize())
+            .and_then(|()| window.set_focus());
+        if let Err(err) = shown {
+            eprintln!("failed to show window `{label}`: {err}");
+        }
+        if let Err(err) = window.emit("window://quit-requested", ()) {
+            // as when closing, better to quit than to be unable to
+            eprintln!("failed to emit window://quit-requested: {err}");
+            break;
+        }
+        *unsaved.asked_to_quit.lock().unwrap() = Some(label);
+        return;
+    }
+    app.exit(0);
+}
+
+/// Marks the calling window as having unsaved changes or not, and shows it
+/// in the title.
+///
+/// Needs no capability: it only affects the calling window.
+#[tauri::command]
+pub fn set_unsaved(window: Window, unsaved: State<Unsaved>, dirty: bool) -> Result<()> {
+    {
+        let mut labels = unsaved.dirty.lock().unwrap();
+        if dirty {
+            labels.insert(window.label().to_owned());
+        } else {
+            labels.remove(window.label());
+        }
+    }
+    let title = window.title()?;
+    let title = title.strip_prefix(MARKER).unwrap_or(&title);
+    if dirty {
+        window.set_title(&format!("{MARKER}{title}"))?;
+    } else {
+        window.set_title(title)?;
+    }
+    Ok(())
+}
+
+/// Goes on quitting after the calling window was asked to, discarding its
+/// unsaved changes.
+///
+/// Needs no capability, but only works for the window [`quit`] asked.
+#[tauri::command]
+pub fn quit_app(window: Window, unsaved: State<Unsaved>) -> Result<()> {
+    let asked = unsaved.asked_to_quit.lock().unwrap().take();
+    if asked.as_deref() != Some(window.label()) {
+        return Err(Error::Forbidden(format!(
+            "window `{}` wasn't asked to quit",
+            window.label()
+        )));
+    }
+    unsaved.forget(window.label());
+    quit(&window.app_handle());
+    Ok(())
+}
+
+/// Closes the calling window, discarding its unsaved changes.
+#[tauri::command]
+pub fn close_window(window: Window, unsaved: State<Unsaved>) -> Result<()> {
+    unsaved.forget(window.label());
+    window.close()?;
+    Ok(())
+}
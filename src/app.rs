@@ -1,4 +1,3 @@
-use gloo_timers::future::TimeoutFuture;
 use leptos::error::Error;
 // use leptos::html::{button, div, span};
 use leptos::leptos_dom::ev::{Event, MouseEvent, SubmitEvent};
@@ -6,25 +5,65 @@ use leptos::leptos_dom::{ErrorKey, Errors};
 use leptos::*;
 use leptos_router::*;
 use serde::{Deserialize, Serialize};
-use serde_wasm_bindgen::to_value;
-use wasm_bindgen::prelude::*;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "tauri"])]
-    async fn invoke(cmd: &str, args: JsValue) -> JsValue;
-}
+use store_derive::Store;
+use uuid::Uuid;
+
+use crate::auth::{provide_capabilities, use_can, Capability};
+use crate::calendar::CalendarPage;
+use crate::commands::{provide_commands, register_command, Command};
+use crate::grid::DataGridPage;
+use crate::history::{provide_history, use_history};
+use crate::ipc::{self, NoArgs};
+use crate::jobs::{create_job_resource, provide_jobs, JobList, JobSpec};
+use crate::kanban::KanbanPage;
+use crate::kv::create_persisted_signal;
+use crate::menu::MenuBridge;
+use crate::modal::{provide_modals, use_modals, ModalHost};
+use crate::notes::NotesPage;
+use crate::outbox::{provide_outbox, use_outbox, Mutation, PendingChanges};
+use crate::palette::CommandPalette;
+use crate::query::{create_command_query_with_attempts, provide_query_client, use_query_client};
+use crate::reminders::ReminderHost;
+use crate::search::SearchPage;
+use crate::settings::{provide_settings, use_settings};
+use crate::shortcuts::{provide_shortcuts, register_shortcut, Scope, ShortcutHost};
+use crate::theme::{provide_appearance, use_appearance, Theme};
+use crate::toast::{provide_toasts, use_toasts, NotificationHistory, ToastHost, ToastLevel};
+use crate::todos::{create_todos, queue_undoable, TodoList};
+use crate::unsaved::{provide_unsaved, register_unsaved, use_unsaved, UnsavedGuard};
 
 #[derive(Serialize, Deserialize)]
 struct GreetArgs<'a> {
     name: &'a str,
 }
 
+#[derive(Clone, PartialEq, Serialize)]
+struct LoadDataArgs {
+    value: i32,
+}
+
+/// Top-level routes, with the title the command palette shows for each.
+const ROUTES: &[(&str, &str)] = &[
+    ("/", "Form example"),
+    ("/calendar", "Calendar"),
+    ("/grid", "Data grid"),
+    ("/kanban", "Board"),
+    ("/notes", "Notes"),
+    ("/search", "Search"),
+];
+
 /// struct for NestedSignals example
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Store)]
 struct DatabaseEntry {
     key: String,
-    value: RwSignal<i32>,
+    value: i32,
+}
+
+/// The entries of the NestedSignals example, each with its own signals.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Store)]
+struct Database {
+    #[store(key = key)]
+    entries: Vec<DatabaseEntry>,
 }
 
 /// struct for MemorySlices example
@@ -36,6 +75,18 @@ struct DatabaseEntry2 {
 
 #[component]
 pub fn App() -> impl IntoView {
+    provide_capabilities();
+    provide_toasts();
+    provide_modals();
+    provide_unsaved();
+    provide_appearance();
+    provide_commands();
+    provide_history();
+    provide_outbox();
+    provide_query_client();
+    provide_settings();
+    provide_shortcuts();
+    provide_jobs();
     let (name, set_name) = create_signal(String::new());
     let (greet_msg, set_greet_msg) = create_signal(String::new());
 
@@ -52,9 +103,11 @@ pub fn App() -> impl IntoView {
                 return;
             }
 
-            let args = to_value(&GreetArgs { name: &name }).unwrap();
             // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
-            let new_msg = invoke("greet", args).await.as_string().unwrap();
+            let new_msg = match ipc::call::<_, String>("greet", &GreetArgs { name: &name }).await {
+                Ok(msg) => msg,
+                Err(err) => err.to_string(),
+            };
             set_greet_msg.set(new_msg);
         });
     };
@@ -62,8 +115,19 @@ pub fn App() -> impl IntoView {
     view! {
         <Router>
             <main class="container">
+                <nav class="routes">
+                    {ROUTES
+                        .iter()
+                        .map(|(path, title)| view! { <A href=*path exact=true>{*title}</A> })
+                        .collect_view()}
+                </nav>
                 <Routes>
                     <Route path="" view=FormExample/>
+                    <Route path="/calendar" view=CalendarPage/>
+                    <Route path="/grid" view=DataGridPage/>
+                    <Route path="/kanban" view=KanbanPage/>
+                    <Route path="/notes" view=NotesPage/>
+                    <Route path="/search" view=SearchPage/>
                 </Routes>
                 <div class="row">
                     <a href="https://tauri.app" target="_blank">
@@ -114,13 +178,86 @@ pub fn App() -> impl IntoView {
                 <CreateEffect/>
                 <CreateResource/>
                 <SuspenseComponent/>
+                <JobList/>
                 <CreateAction/>
+                <OpenViewerWindow/>
             </main>
+            <NotificationHistory/>
+            <ToastHost/>
+            <ReminderHost/>
+            <ModalHost/>
+            <ShortcutHost/>
+            <AppCommands/>
+            <CommandPalette/>
+            <MenuBridge/>
+            <UnsavedGuard/>
         </Router>
     }
     // Counter(0,2)
 }
 
+/// Commands available everywhere. Rendered inside the router so navigation
+/// works.
+#[component]
+fn AppCommands() -> impl IntoView {
+    let navigate = use_navigate();
+    let unsaved = use_unsaved();
+    for (path, title) in ROUTES {
+        let navigate = navigate.clone();
+        register_command(Command::new(
+            format!("navigate:{path}"),
+            format!("Go to {title}"),
+            move || {
+                let navigate = navigate.clone();
+                unsaved.guard(move || navigate(path, Default::default()));
+            },
+        ));
+    }
+    let appearance = use_appearance();
+    let dark = Signal::derive(move || appearance.theme().get() == Theme::Dark);
+    register_command(
+        Command::new("theme.toggle", "Toggle dark theme", move || {
+            appearance.toggle_theme()
+        })
+        .checked(dark),
+    );
+    register_shortcut("theme.toggle", "Ctrl+Shift+L", Scope::Global);
+
+    let settings = use_settings();
+    let close_to_tray = Signal::derive(move || settings.get().with(|s| s.close_to_tray));
+    register_command(
+        Command::new("settings.close_to_tray", "Close to tray", move || {
+            settings.update(|settings| settings.close_to_tray = !settings.close_to_tray)
+        })
+        .enabled(use_can(Capability::SettingsWrite))
+        .checked(close_to_tray),
+    );
+
+    let history = use_history();
+    register_command(
+        Command::new("edit.undo", "Undo", move || history.undo()).enabled(history.can_undo()),
+    );
+    register_command(
+        Command::new("edit.redo", "Redo", move || history.redo()).enabled(history.can_redo()),
+    );
+
+    let modals = use_modals();
+    register_command(Command::new("help.about", "About", move || {
+        modals.open(
+            |handle| {
+                view! {
+                    <h2>"Tauri + Leptos App"</h2>
+                    <p>"Version " {env!("CARGO_PKG_VERSION")}</p>
+                    <div class="modal-buttons">
+                        <button on:click=move |_| handle.close()>"Close"</button>
+                    </div>
+                }
+            },
+            || {},
+        );
+    }));
+}
+
 #[component]
 fn DynamicList(
     /// The number of counters to begin with.
@@ -147,6 +284,7 @@ fn DynamicList(
     // this way, we'll be able to modify the list over time,
     // adding and removing counters, and it will change reactively
     let counters = create_rw_signal(initial_counters);
+    let modals = use_modals();
 
     let add_counter = move |_| {
         // create a signal for the new counter
@@ -180,22 +318,38 @@ fn DynamicList(
                     // `children` receives each item from your `each` iterator
                     // and returns a view
                     children=move |(id, count)| {
+                        // unregistered when the counter is removed
+                        let command = format!("counter.increment:{id}");
+                        register_command(Command::new(
+                            command.clone(),
+                            format!("Increment counter {}", id + 1),
+                            move || count.update(|n| *n += 1),
+                        ));
+                        // "I" increments whichever counter has focus
+                        let row = create_node_ref::<html::Li>();
+                        let scope = Scope::component(format!("counter {id}"), row);
+                        register_shortcut(command, "I", scope);
                         view! {
-                            <li>
+                            <li node_ref=row>
                                 <button on:click=move |_| {
                                     count.update(|n| *n += 1)
                                 }>{count}</button>
                                 <button on:click=move |_| {
-                                    counters
-                                        .update(|counters| {
-                                            counters
-                                                .retain(|(counter_id, signal)| {
-                                                    if counter_id == &id {
-                                                        signal.dispose();
-                                                    }
-                                                    counter_id != &id
-                                                })
-                                        });
+                                    spawn_local(async move {
+                                        if !modals.confirm("Remove this counter?").await {
+                                            return;
+                                        }
+                                        counters
+                                            .update(|counters| {
+                                                counters
+                                                    .retain(|(counter_id, signal)| {
+                                                        if counter_id == &id {
+                                                            signal.dispose();
+                                                        }
+                                                        counter_id != &id
+                                                    })
+                                            });
+                                    })
                                 }>
 
                                     "Remove"
@@ -213,34 +367,42 @@ fn DynamicList(
 // nested signal
 #[component]
 pub fn NestedSignals() -> impl IntoView {
-    let default_data = vec![
-        DatabaseEntry {
-            key: "foo".to_owned(),
-            value: create_rw_signal(10),
-        },
-        DatabaseEntry {
-            key: "bar".to_owned(),
-            value: create_rw_signal(20),
-        },
-        DatabaseEntry {
-            key: "baz".to_owned(),
-            value: create_rw_signal(15),
-        },
-    ];
+    let default_data = Database {
+        entries: vec![
+            DatabaseEntry {
+                key: "foo".to_owned(),
+                value: 10,
+            },
+            DatabaseEntry {
+                key: "bar".to_owned(),
+                value: 20,
+            },
+            DatabaseEntry {
+                key: "baz".to_owned(),
+                value: 15,
+            },
+        ],
+    };
 
-    let data = create_rw_signal(default_data);
+    let data = DatabaseStore::new(default_data.clone());
+    // reads every field, so it follows any change
+    let total = move || data.get().entries.iter().map(|entry| entry.value).sum::<i32>();
     view! {
         <button on:click=move |_| {
-            data.with(|data| {
-                for row in data {
-                    row.value.update(|value| *value *= 2);
-                }
-            });
-            leptos_dom::log!("{:?}", data.get());
+            data.entries()
+                .with(|entries| {
+                    for entry in entries {
+                        entry.value().update(|value| *value *= 2);
+                    }
+                });
+            leptos_dom::log!("{:?}", data.get_untracked());
         }>"Update Values"</button>
-        <For each=move || data.get() key=|state| state.key.to_owned() let:child>
-            <p>{child.value}</p>
+        // keeps the entries' stores, so only the values re-render
+        <button on:click=move |_| data.set(default_data.clone())>"Reset Values"</button>
+        <For each=move || data.entries().get() key=|entry| *entry let:child>
+            <p>{child.value()}</p>
         </For>
+        <p>"Total: " {total}</p>
     }
 }
 
@@ -289,7 +451,7 @@ pub fn MemorySlices() -> impl IntoView {
 
 #[component]
 pub fn ControlledInputs() -> impl IntoView {
-    let name = create_rw_signal("Controlled".to_owned());
+    let name = create_persisted_signal("examples.controlled.name", "Controlled".to_owned());
     view! {
         <input
             type="text"
@@ -305,7 +467,7 @@ pub fn ControlledInputs() -> impl IntoView {
 
 #[component]
 pub fn UncontrolledInputs() -> impl IntoView {
-    let name = create_rw_signal("Uncontrolled".to_owned());
+    let name = create_persisted_signal("examples.uncontrolled.name", "Uncontrolled".to_owned());
     let input_el: NodeRef<html::Input> = create_node_ref();
     let on_submit = move |ev: SubmitEvent| {
         ev.prevent_default();
@@ -324,6 +486,12 @@ pub fn UncontrolledInputs() -> impl IntoView {
 #[component]
 fn TextArea() -> impl IntoView {
     let some_value = create_rw_signal("TextArea".to_owned());
+    // what's typed but not yet submitted
+    let draft = create_rw_signal(some_value.get_untracked());
+    register_unsaved(
+        "Text area",
+        Signal::derive(move || draft.with(|draft| some_value.with(|value| draft != value))),
+    );
     let input_el: NodeRef<html::Textarea> = create_node_ref();
     let on_submit = move |ev: SubmitEvent| {
         ev.prevent_default();
@@ -334,8 +502,8 @@ fn TextArea() -> impl IntoView {
         <form on:submit=on_submit>
             <textarea
                 type="text"
-                prop:value=some_value
-                on:input=move |ev| some_value.update(|v| *v = event_target_value(&ev))
+                prop:value=draft
+                on:input=move |ev| draft.update(|v| *v = event_target_value(&ev))
                 node_ref=input_el
             >// {untrack(move||some_value.get())}
             </textarea>
@@ -356,7 +524,7 @@ fn SelectOption(is: &'static str, value: RwSignal<String>) -> impl IntoView {
 
 #[component]
 fn SelectBar() -> impl IntoView {
-    let value = create_rw_signal("B".to_owned());
+    let value = create_persisted_signal("examples.select", "B".to_owned());
     view! {
         <select on:change=move |ev| {
             value.update(|v| *v = event_target_value(&ev));
@@ -444,7 +612,7 @@ fn ErrorHandling() -> impl IntoView {
 
 #[component]
 fn ParentChildCom() -> impl IntoView {
-    let toggle = create_rw_signal(false);
+    let toggle = create_persisted_signal("examples.toggle", false);
     provide_context(toggle);
     view! {
         <p>"Toggled? " {toggle}</p>
@@ -542,7 +710,7 @@ pub fn WrapsChildren(children: Children) -> impl IntoView {
 
 #[component]
 fn WatchSignal() -> impl IntoView {
-    let num = create_rw_signal(0);
+    let num = create_persisted_signal("examples.watch.num", 0);
     provide_context(num);
     view! {
         <SetButton/>
@@ -578,8 +746,8 @@ fn SetButton() -> impl IntoView {
 
 #[component]
 fn CreateEffect() -> impl IntoView {
-    let t_a = create_rw_signal(0);
-    let t_b = create_rw_signal(0);
+    let t_a = create_persisted_signal("examples.effect.a", 0);
+    let t_b = create_persisted_signal("examples.effect.b", 0);
     create_effect(move |_| {
         leptos_dom::log!("value of a: {:?}", t_a.get());
     });
@@ -591,40 +759,44 @@ fn CreateEffect() -> impl IntoView {
     }
 }
 
-async fn load_data(value: i32) -> i32 {
-    TimeoutFuture::new(1000).await;
-    value * 10
-}
-
 #[component]
 fn CreateResource() -> impl IntoView {
-    let count = create_rw_signal(1);
-    let async_data = create_resource(
-        move || count.get(),
-        |value: i32| async move { load_data(value).await },
-    );
+    let count = create_persisted_signal("examples.resource.count", 1);
+    // counts seen before come straight from the cache, then refresh quietly;
+    // clicking faster than a load takes cancels the stale loads in the backend
+    let (async_data, attempts) =
+        create_command_query_with_attempts::<_, i32>("load_data", move || LoadDataArgs {
+            value: count.get(),
+        });
+    let query_client = use_query_client();
     let increase_count = move |_| count.update(move |count| *count += 1);
+    let decrease_count = move |_| count.update(move |count| *count -= 1);
     view! {
         <h1>"My data"</h1>
         <button on:click=increase_count>"Increment Count"</button>
+        <button on:click=decrease_count>"Decrement Count"</button>
+        <button on:click=move |_| query_client.invalidate("load_data")>"Refresh"</button>
+        {move || (attempts.get() > 1).then(|| view! { <p>"Attempt " {attempts}</p> })}
         {move || match async_data.get() {
             None => view! { <p>"Loading ..."</p> }.into_view(),
-            Some(data) => view! { <p>{data}</p> }.into_view(),
+            Some(Ok(data)) => view! { <p>{data}</p> }.into_view(),
+            Some(Err(err)) => view! { <p class="error">{err.to_string()}</p> }.into_view(),
         }}
     }
 }
 
 #[component]
 fn SuspenseComponent() -> impl IntoView {
-    let count_a = create_rw_signal(2);
-    let count_b = create_rw_signal(5);
-    let a = create_resource(
+    let count_a = create_persisted_signal("examples.suspense.a", 2);
+    let count_b = create_persisted_signal("examples.suspense.b", 5);
+    // each load runs as a backend job and shows up in <JobList/>
+    let a = create_job_resource::<_, i32>(
         move || count_a.get(),
-        |count_a| async move { load_data(count_a).await },
+        |value| JobSpec::LoadData { value },
     );
-    let b = create_resource(
+    let b = create_job_resource::<_, i32>(
         move || count_b.get(),
-        |count_b| async move { load_data(count_b).await },
+        |value| JobSpec::LoadData { value },
     );
     let increase_AB = move |_| {
         count_a.update(move |c| *c += 1);
@@ -634,46 +806,115 @@ fn SuspenseComponent() -> impl IntoView {
         <h1>"My Data"</h1>
         <button on:click=increase_AB>"Increment AB"</button>
         <Suspense fallback=move || view! { <p>"Loading..."</p> }>
-            <h2>"My Data a: "</h2>
-            {move || a.get()}
-            <h2>"My Data b: "</h2>
-            {move || { b.get() }}
+            <ErrorBoundary fallback=errors_fallback>
+                <h2>"My Data a: "</h2>
+                {move || a.get()}
+                <h2>"My Data b: "</h2>
+                {move || { b.get() }}
+            </ErrorBoundary>
         </Suspense>
     }
 }
 
-async fn add_todo(text: &str) -> uuid::Uuid {
-    _ = text;
-    TimeoutFuture::new(1000).await;
-    uuid::Uuid::new_v4()
+/// Lists every error caught by an `<ErrorBoundary/>`.
+fn errors_fallback(errors: RwSignal<Errors>) -> impl IntoView {
+    view! {
+        <div class="error">
+            <ul>
+                {move || {
+                    errors
+                        .get()
+                        .into_iter()
+                        .map(|(_, e)| view! { <li>{e.to_string()}</li> })
+                        .collect_view()
+                }}
+
+            </ul>
+        </div>
+    }
 }
 
 #[component]
 fn CreateAction() -> impl IntoView {
-    let action1 = create_action(|input: &String| {
-        let input = input.to_owned();
-        async move { add_todo(&input).await }
-    });
+    let (loaded, todos) = create_todos();
+
+    let outbox = use_outbox();
+    let action1 = outbox.action();
+    let history = use_history();
+    let toasts = use_toasts();
     let submitted = action1.input();
     let pending = action1.pending();
-    let todo_id = action1.value();
+    let can_write = use_can(Capability::TodoWrite);
 
     let input_ref = create_node_ref::<html::Input>();
+    register_command(
+        Command::new("todo.add", "Add todo", move || {
+            if let Some(input) = input_ref.get() {
+                _ = input.focus();
+            }
+        })
+        .enabled(can_write),
+    );
+    register_shortcut("todo.add", "Alt+N", Scope::Global);
 
     view! {
         <form on:submit=move |ev| {
             ev.prevent_default();
             let input = input_ref.get().expect("input to exist");
-            action1.dispatch(input.value());
+            let id = Uuid::new_v4();
+            let text = input.value();
+            queue_undoable(
+                outbox,
+                history,
+                toasts,
+                ToastLevel::Success,
+                "Todo added",
+                Mutation::AddTodo {
+                    id,
+                    text,
+                    details: Default::default(),
+                },
+                Mutation::DeleteTodo { id },
+            );
         }>
 
             <label>"What do you need to do?" <input type="text" node_ref=input_ref/></label>
-            <button type="submit">"Add Todo"</button>
+            <button type="submit" disabled=move || !can_write.get()>
+                "Add Todo"
+            </button>
         </form>
-        <p>{move || pending.get().then(|| "Loading...")}</p>
         <p>"Submitted: " <code>{move || format!("{:#?}", submitted.get())}</code></p>
         <p>"Pending: " <code>{move || format!("{:#?}", pending.get())}</code></p>
-        <p>"Todo ID: " <code>{move || format!("{:#?}", todo_id.get())}</code></p>
+        <PendingChanges/>
+        <ErrorBoundary fallback=errors_fallback>
+            {move || loaded.get().map(|res| res.map(|_| ()))}
+            <TodoList todos/>
+        </ErrorBoundary>
+    }
+}
+
+#[component]
+fn OpenViewerWindow() -> impl IntoView {
+    let can_open = use_can(Capability::WindowOpen);
+    let open = create_action(|_: &()| async move {
+        ipc::call::<_, String>("open_viewer_window", &NoArgs {}).await
+    });
+    register_command(
+        Command::new("window.open_viewer", "Open read-only window", move || {
+            open.dispatch(())
+        })
+        .enabled(can_open),
+    );
+    view! {
+        <Show when=move || can_open.get()>
+            <button on:click=move |_| open.dispatch(())>"Open read-only window"</button>
+        </Show>
+        {move || {
+            open.value()
+                .get()
+                .and_then(Result::err)
+                .map(|err| view! { <p class="error">{err.to_string()}</p> })
+        }}
     }
 }
 
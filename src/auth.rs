@@ -0,0 +1,55 @@
+use leptos::*;
+use serde::Deserialize;
+
+use crate::ipc::{self, NoArgs};
+
+/// Mirrors the backend's capabilities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    Greet,
+    TodoRead,
+    TodoWrite,
+    TodoDelete,
+    WindowOpen,
+    JobRun,
+    JobRead,
+    DataRead,
+    DataWrite,
+    Notify,
+    NotifyClear,
+    SettingsRead,
+    SettingsWrite,
+    MenuUpdate,
+    NoteRead,
+    NoteWrite,
+    NoteDelete,
+    Search,
+    RequestCancel,
+}
+
+/// What the current window was granted, fetched once at startup.
+#[derive(Clone, Copy)]
+pub struct Capabilities(Resource<(), Vec<Capability>>);
+
+pub fn provide_capabilities() {
+    let granted = create_resource(
+        || (),
+        |_| async move {
+            ipc::call::<_, Vec<Capability>>("my_capabilities", &NoArgs {})
+                .await
+                .unwrap_or_default()
+        },
+    );
+    provide_context(Capabilities(granted));
+}
+
+/// Whether the current window may use `capability`.
+///
+/// This only decides what to show; the backend checks every call anyway.
+pub fn use_can(capability: Capability) -> Signal<bool> {
+    let Capabilities(granted) = expect_context::<Capabilities>();
+    Signal::derive(move || {
+        granted.with(|caps| caps.as_ref().is_some_and(|caps| caps.contains(&capability)))
+    })
+}
@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+
+use chrono::{Days, Local, Months, NaiveDate, Weekday};
+use leptos::ev::{DragEvent, KeyboardEvent};
+use leptos::*;
+use uuid::Uuid;
+
+use crate::auth::{use_can, Capability};
+use crate::history::use_history;
+use crate::ipc::NoArgs;
+use crate::outbox::{use_outbox, Mutation};
+use crate::query::create_command_query;
+use crate::reminders::Reminder;
+use crate::toast::{use_toasts, ToastLevel};
+use crate::todos::{create_todos, queue_undoable, Todo};
+
+const FIRST_WEEKDAY: Weekday = Weekday::Mon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CalendarView {
+    Month,
+    Week,
+    Day,
+}
+
+impl CalendarView {
+    const ALL: [CalendarView; 3] = [CalendarView::Month, CalendarView::Week, CalendarView::Day];
+
+    fn label(self) -> &'static str {
+        match self {
+            CalendarView::Month => "Month",
+            CalendarView::Week => "Week",
+            CalendarView::Day => "Day",
+        }
+    }
+
+    /// The days shown around `day`.
+    fn days(self, day: NaiveDate) -> Vec<NaiveDate> {
+        match self {
+            CalendarView::Month => dates::month_grid(day, FIRST_WEEKDAY),
+            CalendarView::Week => dates::week(day, FIRST_WEEKDAY),
+            CalendarView::Day => vec![day],
+        }
+    }
+
+    /// `day` moved a whole page, back if `forward` is false.
+    fn page(self, day: NaiveDate, forward: bool) -> Option<NaiveDate> {
+        match (self, forward) {
+            (CalendarView::Month, true) => day.checked_add_months(Months::new(1)),
+            (CalendarView::Month, false) => day.checked_sub_months(Months::new(1)),
+            (CalendarView::Week, true) => day.checked_add_days(Days::new(7)),
+            (CalendarView::Week, false) => day.checked_sub_days(Days::new(7)),
+            (CalendarView::Day, true) => day.succ_opt(),
+            (CalendarView::Day, false) => day.pred_opt(),
+        }
+    }
+
+    fn title(self, day: NaiveDate) -> String {
+        match self {
+            CalendarView::Month => day.format("%B %Y").to_string(),
+            CalendarView::Week => {
+                let start = dates::week_start(day, FIRST_WEEKDAY);
+                start.format("Week of %-d %B %Y").to_string()
+            }
+            CalendarView::Day => day.format("%A %-d %B %Y").to_string(),
+        }
+    }
+}
+
+/// What's on each day, by local date.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Agenda {
+    /// Soonest first.
+    todos: BTreeMap<NaiveDate, Vec<Todo>>,
+    reminders: BTreeMap<NaiveDate, Vec<Reminder>>,
+}
+
+fn cell_id(day: NaiveDate) -> String {
+    format!("calendar-{day}")
+}
+
+/// Todos by due date, with their upcoming reminders, in month, week and day
+/// views. Dragging a todo to another day reschedules it at the same time of
+/// day; the arrow keys move between days.
+#[component]
+pub fn CalendarPage() -> impl IntoView {
+    let view_kind = create_rw_signal(CalendarView::Month);
+    let today = Local::now().date_naive();
+    let cursor = create_rw_signal(today);
+    let (_, todos) = create_todos();
+    let reminders = create_command_query::<_, Vec<Reminder>>("list_reminders", || NoArgs {});
+    let agenda = create_memo(move |_| {
+        let mut agenda = Agenda::default();
+        for todo in todos.get() {
+            if let Some(due) = todo.details.due {
+                let day = dates::day_of(due, &Local);
+                agenda.todos.entry(day).or_default().push(todo);
+            }
+        }
+        for todos in agenda.todos.values_mut() {
+            todos.sort_by_key(|todo| todo.details.due);
+        }
+        for reminder in reminders.get().and_then(Result::ok).unwrap_or_default() {
+            let day = dates::day_of(reminder.fires_at, &Local);
+            agenda.reminders.entry(day).or_default().push(reminder);
+        }
+        agenda
+    });
+    let days = create_memo(move |_| view_kind.get().days(cursor.get()));
+
+    let dragging = create_rw_signal(None::<Uuid>);
+    let outbox = use_outbox();
+    let history = use_history();
+    let toasts = use_toasts();
+    let reschedule = Callback::new(move |day: NaiveDate| {
+        let Some(id) = dragging.get_untracked() else {
+            return;
+        };
+        dragging.set(None);
+        let Some(todo) = todos.with_untracked(|todos| todos.iter().find(|t| t.id == id).cloned())
+        else {
+            return;
+        };
+        let Some(due) = todo.details.due else {
+            return;
+        };
+        let moved = dates::move_to_day(due, day, &Local);
+        if moved.is_none() || moved == Some(due) {
+            return;
+        }
+        let mut details = todo.details.clone();
+        details.due = moved;
+        let change = Mutation::UpdateTodo {
+            id,
+            text: todo.text.clone(),
+            details,
+        };
+        let inverse = Mutation::UpdateTodo {
+            id,
+            text: todo.text,
+            details: todo.details,
+        };
+        let level = ToastLevel::Success;
+        queue_undoable(outbox, history, toasts, level, "Todo rescheduled", change, inverse);
+    });
+
+    let go = move |day: Option<NaiveDate>| {
+        if let Some(day) = day {
+            cursor.set(day);
+        }
+    };
+    let page = move |forward: bool| {
+        go(view_kind.get_untracked().page(cursor.get_untracked(), forward))
+    };
+    let on_keydown = move |ev: KeyboardEvent| {
+        let day = cursor.get_untracked();
+        let vertical = match view_kind.get_untracked() {
+            CalendarView::Day => 1,
+            CalendarView::Month | CalendarView::Week => 7,
+        };
+        let target = match ev.key().as_str() {
+            "ArrowLeft" => day.pred_opt(),
+            "ArrowRight" => day.succ_opt(),
+            "ArrowUp" => day.checked_sub_days(Days::new(vertical)),
+            "ArrowDown" => day.checked_add_days(Days::new(vertical)),
+            "PageUp" => view_kind.get_untracked().page(day, false),
+            "PageDown" => view_kind.get_untracked().page(day, true),
+            "Home" => Some(Local::now().date_naive()),
+            "Enter" => {
+                view_kind.set(CalendarView::Day);
+                None
+            }
+            "Escape" if view_kind.get_untracked() == CalendarView::Day => {
+                view_kind.set(CalendarView::Month);
+                None
+            }
+            _ => return,
+        };
+        ev.prevent_default();
+        go(target);
+    };
+
+    view! {
+        <section class="calendar">
+            <header>
+                <button on:click=move |_| page(false) aria-label="Previous">
+                    "‹"
+                </button>
+                <button on:click=move |_| go(Some(Local::now().date_naive()))>"Today"</button>
+                <button on:click=move |_| page(true) aria-label="Next">
+                    "›"
+                </button>
+                <h2>{move || view_kind.get().title(cursor.get())}</h2>
+                <div class="calendar-views" role="group" aria-label="View">
+                    {CalendarView::ALL
+                        .into_iter()
+                        .map(|kind| {
+                            view! {
+                                <button
+                                    class:active=move || view_kind.get() == kind
+                                    on:click=move |_| view_kind.set(kind)
+                                >
+                                    {kind.label()}
+                                </button>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+            </header>
+            <Show when=move || view_kind.get() != CalendarView::Day>
+                <div class="calendar-weekdays" aria-hidden="true">
+                    {dates::week(today, FIRST_WEEKDAY)
+                        .into_iter()
+                        .map(|day| view! { <span>{day.format("%a").to_string()}</span> })
+                        .collect_view()}
+                </div>
+            </Show>
+            <div
+                class=move || {
+                    format!("calendar-grid {}", view_kind.get().label().to_lowercase())
+                }
+                role="grid"
+                tabindex="0"
+                aria-label="Calendar"
+                aria-activedescendant=move || cell_id(cursor.get())
+                on:keydown=on_keydown
+            >
+                <For
+                    each=move || days.get()
+                    key=|day| *day
+                    children=move |day| {
+                        view! { <DayCell day cursor view_kind agenda dragging reschedule/> }
+                    }
+                />
+
+            </div>
+        </section>
+    }
+}
+
+#[component]
+fn DayCell(
+    day: NaiveDate,
+    cursor: RwSignal<NaiveDate>,
+    view_kind: RwSignal<CalendarView>,
+    agenda: Memo<Agenda>,
+    dragging: RwSignal<Option<Uuid>>,
+    reschedule: Callback<NaiveDate>,
+) -> impl IntoView {
+    let can_write = use_can(Capability::TodoWrite);
+    let todos = move || agenda.with(|a| a.todos.get(&day).cloned().unwrap_or_default());
+    let reminders = move || agenda.with(|a| a.reminders.get(&day).cloned().unwrap_or_default());
+    let outside = move || {
+        view_kind.get() == CalendarView::Month && !dates::same_month(day, cursor.get())
+    };
+    let label = move || match view_kind.get() {
+        CalendarView::Month => day.format("%-d").to_string(),
+        CalendarView::Week | CalendarView::Day => day.format("%a %-d").to_string(),
+    };
+
+    view! {
+        <div
+            id=cell_id(day)
+            class="calendar-day"
+            role="gridcell"
+            aria-selected=move || (cursor.get() == day).to_string()
+            class:selected=move || cursor.get() == day
+            class:today=move || day == Local::now().date_naive()
+            class:outside=outside
+            on:click=move |_| cursor.set(day)
+            on:dblclick=move |_| {
+                cursor.set(day);
+                view_kind.set(CalendarView::Day);
+            }
+            on:dragover=move |ev: DragEvent| {
+                if dragging.with(Option::is_some) {
+                    ev.prevent_default();
+                }
+            }
+            on:drop=move |ev: DragEvent| {
+                ev.prevent_default();
+                reschedule.call(day);
+            }
+        >
+            <span class="calendar-date">{label}</span>
+            <ul>
+                <For
+                    each=todos
+                    key=|todo| todo.clone()
+                    children=move |todo| {
+                        let id = todo.id;
+                        let time = todo
+                            .details
+                            .due
+                            .map(|due| due.with_timezone(&Local).format("%H:%M").to_string());
+                        view! {
+                            <li
+                                class="calendar-todo"
+                                class:done=todo.done
+                                class:pending=todo.pending
+                                draggable=move || if can_write.get() { "true" } else { "false" }
+                                on:dragstart=move |ev: DragEvent| {
+                                    if let Some(data) = ev.data_transfer() {
+                                        _ = data.set_data("text/plain", &id.to_string());
+                                        data.set_effect_allowed("move");
+                                    }
+                                    dragging.set(Some(id));
+                                }
+                                on:dragend=move |_| dragging.set(None)
+                            >
+                                <time>{time}</time>
+                                " "
+                                {todo.text}
+                            </li>
+                        }
+                    }
+                />
+
+                <For
+                    each=reminders
+                    key=|reminder| reminder.clone()
+                    children=|reminder| {
+                        let time = reminder.fires_at.with_timezone(&Local).format("%H:%M");
+                        view! {
+                            <li class="calendar-reminder">
+                                "🔔 " <time>{time.to_string()}</time> " " {reminder.text}
+                            </li>
+                        }
+                    }
+                />
+
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,124 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use leptos::*;
+use serde::Serialize;
+
+use crate::ipc;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelRequestArgs<'a> {
+    request_id: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenState {
+    Running,
+    Finished,
+    Cancelled,
+}
+
+/// Identifies one backend call so it can be cancelled.
+///
+/// Pass [`CancelToken::request_id`] to the command as `requestId`; calling
+/// [`CancelToken::cancel`] then tells the backend to stop that call.
+#[derive(Clone)]
+pub struct CancelToken {
+    request_id: Rc<str>,
+    state: Rc<Cell<TokenState>>,
+    children: Rc<RefCell<Vec<CancelToken>>>,
+    on_cancel: Rc<RefCell<Vec<Box<dyn FnOnce()>>>>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self {
+            request_id: uuid::Uuid::new_v4().to_string().into(),
+            state: Rc::new(Cell::new(TokenState::Running)),
+            children: Default::default(),
+            on_cancel: Default::default(),
+        }
+    }
+
+    /// A token for a sub-call that is cancelled along with this one.
+    pub fn child(&self) -> CancelToken {
+        let child = CancelToken::new();
+        match self.state.get() {
+            TokenState::Cancelled => child.state.set(TokenState::Cancelled),
+            _ => self.children.borrow_mut().push(child.clone()),
+        }
+        child
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.get() == TokenState::Cancelled
+    }
+
+    /// Does nothing once the call has finished or was already cancelled.
+    pub fn cancel(&self) {
+        if self.state.get() != TokenState::Running {
+            return;
+        }
+        self.state.set(TokenState::Cancelled);
+        for child in self.children.take() {
+            child.cancel();
+        }
+        for callback in self.on_cancel.take() {
+            callback();
+        }
+        let request_id = Rc::clone(&self.request_id);
+        spawn_local(async move {
+            let args = CancelRequestArgs {
+                request_id: &request_id,
+            };
+            if let Err(err) = ipc::call::<_, ()>("cancel_request", &args).await {
+                log::warn!("failed to cancel request {request_id}: {err}");
+            }
+        });
+    }
+
+    /// Calls `callback` once the token is cancelled, right away if it
+    /// already is. Never called once the call has finished.
+    pub fn on_cancel(&self, callback: impl FnOnce() + 'static) {
+        match self.state.get() {
+            TokenState::Running => self.on_cancel.borrow_mut().push(Box::new(callback)),
+            TokenState::Cancelled => callback(),
+            TokenState::Finished => {}
+        }
+    }
+
+    /// Marks the call as finished, so cancelling it no longer reaches the
+    /// backend.
+    pub(crate) fn finish(&self) {
+        if self.state.get() == TokenState::Running {
+            self.state.set(TokenState::Finished);
+            self.on_cancel.take();
+        }
+    }
+
+    /// Whether `other` is a clone of this token.
+    pub(crate) fn same(&self, other: &CancelToken) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelToken")
+            .field("request_id", &self.request_id)
+            .field("state", &self.state.get())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
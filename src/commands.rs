@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use leptos::*;
+
+/// How many recently run commands are remembered.
+const MAX_RECENT: usize = 5;
+
+/// Something the user can run by name, e.g. from the command palette.
+#[derive(Clone)]
+pub struct Command {
+    /// Stable across registrations, like `theme.toggle`.
+    pub id: String,
+    pub title: String,
+    pub enabled: Signal<bool>,
+    /// For commands that toggle something, whether it is on.
+    pub checked: Option<Signal<bool>>,
+    run: Rc<dyn Fn()>,
+}
+
+impl Command {
+    pub fn new(id: impl Into<String>, title: impl Into<String>, run: impl Fn() + 'static) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            enabled: Signal::derive(|| true),
+            checked: None,
+            run: Rc::new(run),
+        }
+    }
+
+    pub fn enabled(mut self, enabled: impl Into<Signal<bool>>) -> Self {
+        self.enabled = enabled.into();
+        self
+    }
+
+    pub fn checked(mut self, checked: impl Into<Signal<bool>>) -> Self {
+        self.checked = Some(checked.into());
+        self
+    }
+}
+
+/// The commands registered by mounted components.
+///
+/// Registering an id that is already taken shadows the earlier command until
+/// the new one is unregistered.
+#[derive(Clone, Copy)]
+pub struct Commands {
+    registered: RwSignal<Vec<(u64, Command)>>,
+    next_key: StoredValue<u64>,
+    recent: RwSignal<Vec<String>>,
+}
+
+pub fn provide_commands() {
+    provide_context(Commands {
+        registered: create_rw_signal(Vec::new()),
+        next_key: store_value(0),
+        recent: create_rw_signal(Vec::new()),
+    });
+}
+
+pub fn use_commands() -> Commands {
+    expect_context::<Commands>()
+}
+
+/// Registers `command` until the current reactive scope is disposed, e.g.
+/// when the component that registered it unmounts.
+pub fn register_command(command: Command) {
+    let commands = use_commands();
+    let key = commands.register(command);
+    on_cleanup(move || commands.unregister(key));
+}
+
+impl Commands {
+    /// Prefer [`register_command`], which also unregisters.
+    pub fn register(&self, command: Command) -> u64 {
+        let key = self.next_key.get_value();
+        self.next_key.set_value(key + 1);
+        self.registered
+            .update(|registered| registered.push((key, command)));
+        key
+    }
+
+    pub fn unregister(&self, key: u64) {
+        self.registered
+            .try_update(|registered| registered.retain(|(k, _)| *k != key));
+    }
+
+    /// The visible commands, in registration order.
+    pub fn list(&self) -> Signal<Vec<Command>> {
+        let registered = self.registered;
+        Signal::derive(move || {
+            registered.with(|registered| {
+                let mut seen = HashSet::new();
+                let mut list = registered
+                    .iter()
+                    .rev()
+                    .filter(|(_, command)| seen.insert(command.id.clone()))
+                    .map(|(_, command)| command.clone())
+                    .collect::<Vec<_>>();
+                list.reverse();
+                list
+            })
+        })
+    }
+
+    pub fn find(&self, id: &str) -> Option<Command> {
+        self.registered.with_untracked(|registered| {
+            registered
+                .iter()
+                .rev()
+                .find(|(_, command)| command.id == id)
+                .map(|(_, command)| command.clone())
+        })
+    }
+
+    /// Runs the command if it is registered and enabled, and returns whether
+    /// it ran.
+    pub fn run(&self, id: &str) -> bool {
+        let Some(command) = self.find(id) else {
+            return false;
+        };
+        if !command.enabled.get_untracked() {
+            return false;
+        }
+        self.recent.update(|recent| {
+            recent.retain(|recent| recent != id);
+            recent.insert(0, id.to_owned());
+            recent.truncate(MAX_RECENT);
+        });
+        (command.run)();
+        true
+    }
+
+    /// Ids of recently run commands, most recent first.
+    pub fn recent(&self) -> Signal<Vec<String>> {
+        self.recent.into()
+    }
+}
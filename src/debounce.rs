@@ -0,0 +1,29 @@
+use std::time::Duration;
+
+use leptos::*;
+
+/// Follows `source`, but only once it has stopped changing for `delay`.
+pub fn create_debounced<T>(source: impl Into<Signal<T>>, delay: Duration) -> Signal<T>
+where
+    T: Clone + PartialEq + 'static,
+{
+    let source = source.into();
+    let debounced = create_rw_signal(source.get_untracked());
+    let pending = store_value(None::<TimeoutHandle>);
+    let cancel = move || {
+        if let Some(handle) = pending.try_update_value(Option::take).flatten() {
+            handle.clear();
+        }
+    };
+    create_effect(move |_| {
+        let value = source.get();
+        cancel();
+        if debounced.with_untracked(|debounced| *debounced == value) {
+            return;
+        }
+        let handle = set_timeout_with_handle(move || debounced.set(value), delay);
+        pending.set_value(handle.ok());
+    });
+    on_cleanup(cancel);
+    debounced.into()
+}
@@ -0,0 +1,110 @@
+/// One line of a line-level diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Added(&'a str),
+    Removed(&'a str),
+}
+
+/// Diffs `old` against `new` line by line, keeping their longest common
+/// subsequence of lines. Removed lines come before the lines added in their
+/// place.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    // an edit usually touches a few lines in the middle, so trimming what's
+    // shared at both ends keeps the table small
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // common[i][j] is the length of the longest common subsequence of
+    // a[i..] and b[j..]
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::Same(line))
+        .collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            lines.push(DiffLine::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(DiffLine::Removed(a[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(b[j]));
+            j += 1;
+        }
+    }
+    lines.extend(a[i..].iter().map(|line| DiffLine::Removed(line)));
+    lines.extend(b[j..].iter().map(|line| DiffLine::Added(line)));
+    lines.extend(old[old.len() - suffix..].iter().map(|line| DiffLine::Same(line)));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiffLine::{Added, Removed, Same};
+
+    #[test]
+    fn identical_texts_are_all_same() {
+        assert_eq!(diff_lines("a\nb", "a\nb"), [Same("a"), Same("b")]);
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn shared_prefix_and_suffix_are_kept() {
+        assert_eq!(
+            diff_lines("a\nb\nc\nd\ne", "a\nb\nX\nd\ne"),
+            [Same("a"), Same("b"), Removed("c"), Added("X"), Same("d"), Same("e")]
+        );
+    }
+
+    #[test]
+    fn prefix_and_suffix_dont_overlap() {
+        assert_eq!(diff_lines("x\ny\nx", "x\nx"), [Same("x"), Removed("y"), Same("x")]);
+        assert_eq!(diff_lines("x", "x\nx"), [Same("x"), Added("x")]);
+    }
+
+    #[test]
+    fn everything_added() {
+        assert_eq!(diff_lines("", "a\nb"), [Added("a"), Added("b")]);
+    }
+
+    #[test]
+    fn everything_removed() {
+        assert_eq!(diff_lines("a\nb", ""), [Removed("a"), Removed("b")]);
+    }
+
+    #[test]
+    fn removed_lines_come_before_the_added_ones() {
+        assert_eq!(
+            diff_lines("a\nb", "c\nd"),
+            [Removed("a"), Removed("b"), Added("c"), Added("d")]
+        );
+        assert_eq!(
+            diff_lines("keep\nold\nkeep too", "keep\nnew\nkeep too"),
+            [Same("keep"), Removed("old"), Added("new"), Same("keep too")]
+        );
+    }
+}
@@ -0,0 +1,667 @@
+//! Spreadsheet formulas for the data grid, such as `=A1*3` or
+//! `=SUM(B1:B10)/2`.
+//!
+//! A cell whose text starts with `=` is a formula; anything else is a literal
+//! number, text or blank. References name the column by letter and the row
+//! from 1, so `B3` is the third row of the second column.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A row and column, both from 0.
+pub type CellRef = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Blank,
+    Number(f64),
+    Text(String),
+}
+
+impl Value {
+    fn literal(text: &str) -> Self {
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            Value::Blank
+        } else if let Some(number) = trimmed.parse::<f64>().ok().filter(|n| n.is_finite()) {
+            Value::Number(number)
+        } else {
+            Value::Text(text.to_owned())
+        }
+    }
+
+    /// Blanks count as 0, as in spreadsheets.
+    fn number(&self) -> Result<f64, CellError> {
+        match self {
+            Value::Blank => Ok(0.0),
+            Value::Number(number) => Ok(*number),
+            Value::Text(_) => Err(CellError::Value),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Blank => Ok(()),
+            // whole numbers without the `.0` f64 would show
+            Value::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", *number as i64)
+            }
+            Value::Number(number) => write!(f, "{number}"),
+            Value::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// Why a cell has no value, shown in its place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellError {
+    /// The formula doesn't parse.
+    Syntax,
+    /// A reference outside the grid.
+    Ref,
+    /// Text where a number was needed, or a number too big to show.
+    Value,
+    DivideByZero,
+    /// A function that doesn't exist.
+    Name,
+    /// The cell depends on itself, directly or through other cells.
+    Cycle,
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CellError::Syntax => "#ERROR!",
+            CellError::Ref => "#REF!",
+            CellError::Value => "#VALUE!",
+            CellError::DivideByZero => "#DIV/0!",
+            CellError::Name => "#NAME?",
+            CellError::Cycle => "#CYCLE!",
+        })
+    }
+}
+
+/// What a cell shows.
+pub type Evaluated = Result<Value, CellError>;
+
+/// What another cell shows.
+pub type Lookup<'a> = dyn Fn(CellRef) -> Evaluated + 'a;
+
+/// A cell's text, parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Literal(Value),
+    Formula(Result<Expr, CellError>),
+}
+
+impl Content {
+    pub fn parse(text: &str) -> Self {
+        match text.trim_start().strip_prefix('=') {
+            Some(formula) => Content::Formula(Parser::new(formula).parse()),
+            None => Content::Literal(Value::literal(text)),
+        }
+    }
+
+    /// The cells in a grid of `rows` by `cols` this reads.
+    pub fn references(&self, rows: usize, cols: usize) -> BTreeSet<CellRef> {
+        let mut references = BTreeSet::new();
+        if let Content::Formula(Ok(expr)) = self {
+            expr.collect_references(rows, cols, &mut references);
+        }
+        references
+    }
+
+    /// The value in a grid of `rows` by `cols`, reading other cells with
+    /// `lookup`.
+    pub fn evaluate(&self, rows: usize, cols: usize, lookup: &Lookup) -> Evaluated {
+        match self {
+            Content::Literal(value) => Ok(value.clone()),
+            Content::Formula(Err(err)) => Err(*err),
+            // `=A1` with A1 blank shows 0, as in spreadsheets
+            Content::Formula(Ok(expr)) => match expr.evaluate(rows, cols, lookup)? {
+                Value::Blank => Ok(Value::Number(0.0)),
+                value => Ok(value),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sum,
+    Average,
+    Min,
+    Max,
+    Count,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "SUM" => Some(Function::Sum),
+            "AVERAGE" | "AVG" => Some(Function::Average),
+            "MIN" => Some(Function::Min),
+            "MAX" => Some(Function::Max),
+            "COUNT" => Some(Function::Count),
+            _ => None,
+        }
+    }
+
+    fn apply(self, numbers: &[f64]) -> Result<f64, CellError> {
+        let sum = || numbers.iter().sum::<f64>();
+        match self {
+            Function::Sum => Ok(sum()),
+            Function::Average if numbers.is_empty() => Err(CellError::DivideByZero),
+            Function::Average => Ok(sum() / numbers.len() as f64),
+            // no numbers at all is 0, as in spreadsheets
+            Function::Min => Ok(numbers.iter().copied().reduce(f64::min).unwrap_or(0.0)),
+            Function::Max => Ok(numbers.iter().copied().reduce(f64::max).unwrap_or(0.0)),
+            Function::Count => Ok(numbers.len() as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Ref(CellRef),
+    /// Only allowed as a function argument.
+    Range(CellRef, CellRef),
+    Negate(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    fn collect_references(&self, rows: usize, cols: usize, into: &mut BTreeSet<CellRef>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Ref(at) => {
+                into.insert(*at);
+            }
+            Expr::Range(from, to) => into.extend(cells_in(*from, *to, rows, cols)),
+            Expr::Negate(expr) => expr.collect_references(rows, cols, into),
+            Expr::Binary(_, left, right) => {
+                left.collect_references(rows, cols, into);
+                right.collect_references(rows, cols, into);
+            }
+            Expr::Call(_, args) => {
+                for arg in args {
+                    arg.collect_references(rows, cols, into);
+                }
+            }
+        }
+    }
+
+    fn evaluate(&self, rows: usize, cols: usize, lookup: &Lookup) -> Evaluated {
+        let evaluate = |expr: &Expr| expr.evaluate(rows, cols, lookup);
+        let value = match self {
+            Expr::Number(number) => Value::Number(*number),
+            Expr::Ref((row, col)) if *row >= rows || *col >= cols => {
+                return Err(CellError::Ref);
+            }
+            Expr::Ref(at) => lookup(*at)?,
+            Expr::Range(..) => return Err(CellError::Value),
+            Expr::Negate(expr) => Value::Number(-evaluate(expr)?.number()?),
+            Expr::Binary(op, left, right) => {
+                let left = evaluate(left)?.number()?;
+                let right = evaluate(right)?.number()?;
+                Value::Number(match op {
+                    Op::Add => left + right,
+                    Op::Subtract => left - right,
+                    Op::Multiply => left * right,
+                    Op::Divide if right == 0.0 => return Err(CellError::DivideByZero),
+                    Op::Divide => left / right,
+                    Op::Power => left.powf(right),
+                })
+            }
+            Expr::Call(function, args) => {
+                let mut numbers = Vec::new();
+                for arg in args {
+                    match arg {
+                        // text and blanks in a range are skipped, as in
+                        // spreadsheets
+                        Expr::Range(from, to) => {
+                            for at in cells_in(*from, *to, rows, cols) {
+                                match lookup(at)? {
+                                    Value::Number(number) => numbers.push(number),
+                                    Value::Blank | Value::Text(_) => {}
+                                }
+                            }
+                        }
+                        arg => numbers.push(evaluate(arg)?.number()?),
+                    }
+                }
+                Value::Number(function.apply(&numbers)?)
+            }
+        };
+        match value {
+            Value::Number(number) if !number.is_finite() => Err(CellError::Value),
+            value => Ok(value),
+        }
+    }
+}
+
+/// The cells of the range between `from` and `to` that are in a grid of
+/// `rows` by `cols`.
+fn cells_in(
+    from: CellRef,
+    to: CellRef,
+    rows: usize,
+    cols: usize,
+) -> impl Iterator<Item = CellRef> {
+    let last_row = from.0.max(to.0).min(rows.saturating_sub(1));
+    let last_col = from.1.max(to.1).min(cols.saturating_sub(1));
+    let first_col = from.1.min(to.1);
+    (from.0.min(to.0)..=last_row)
+        .flat_map(move |row| (first_col..=last_col).map(move |col| (row, col)))
+}
+
+/// `A` to `Z`, then `AA`, `AB` and so on, as spreadsheets name columns.
+pub fn column_name(col: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = col + 1;
+    while n > 0 {
+        n -= 1;
+        name.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// The column `column_name` names `name`.
+fn column_index(name: &str) -> Option<usize> {
+    let number = name.bytes().try_fold(0usize, |number, letter| {
+        let digit = usize::from(letter.to_ascii_uppercase().checked_sub(b'A')?) + 1;
+        number.checked_mul(26)?.checked_add(digit)
+    })?;
+    number.checked_sub(1)
+}
+
+/// The cells that depend on themselves, given what each cell reads.
+///
+/// Tarjan's algorithm: a cell is in a cycle if its strongly connected
+/// component has more than one cell, or it reads itself.
+pub fn cyclic(graph: &BTreeMap<CellRef, BTreeSet<CellRef>>) -> BTreeSet<CellRef> {
+    struct Search<'a> {
+        graph: &'a BTreeMap<CellRef, BTreeSet<CellRef>>,
+        next_index: usize,
+        index: BTreeMap<CellRef, usize>,
+        low_link: BTreeMap<CellRef, usize>,
+        stack: Vec<CellRef>,
+        on_stack: BTreeSet<CellRef>,
+        cyclic: BTreeSet<CellRef>,
+    }
+
+    impl Search<'_> {
+        fn visit(&mut self, cell: CellRef) {
+            self.index.insert(cell, self.next_index);
+            self.low_link.insert(cell, self.next_index);
+            self.next_index += 1;
+            self.stack.push(cell);
+            self.on_stack.insert(cell);
+            let reads = self.graph.get(&cell);
+            for &next in reads.into_iter().flatten() {
+                if !self.index.contains_key(&next) {
+                    self.visit(next);
+                    let low = self.low_link[&cell].min(self.low_link[&next]);
+                    self.low_link.insert(cell, low);
+                } else if self.on_stack.contains(&next) {
+                    let low = self.low_link[&cell].min(self.index[&next]);
+                    self.low_link.insert(cell, low);
+                }
+            }
+            if self.low_link[&cell] != self.index[&cell] {
+                return;
+            }
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(&member);
+                component.push(member);
+                if member == cell {
+                    break;
+                }
+            }
+            let reads_itself = reads.is_some_and(|reads| reads.contains(&cell));
+            if component.len() > 1 || reads_itself {
+                self.cyclic.extend(component);
+            }
+        }
+    }
+
+    let mut search = Search {
+        graph,
+        next_index: 0,
+        index: BTreeMap::new(),
+        low_link: BTreeMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        cyclic: BTreeSet::new(),
+    };
+    for &cell in graph.keys() {
+        if !search.index.contains_key(&cell) {
+            search.visit(cell);
+        }
+    }
+    search.cyclic
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(formula: &'a str) -> Self {
+        Self {
+            chars: formula.chars().peekable(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Expr, CellError> {
+        let expr = self.expr()?;
+        self.skip_whitespace();
+        match self.chars.peek() {
+            None => Ok(expr),
+            Some(_) => Err(CellError::Syntax),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Takes `expected` if it comes next.
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn expr(&mut self) -> Result<Expr, CellError> {
+        let mut expr = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Subtract
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, CellError> {
+        let mut expr = self.power()?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Multiply
+            } else if self.eat('/') {
+                Op::Divide
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.power()?));
+        }
+    }
+
+    /// `^` binds tighter than `*` and to the right, so `2^3^2` is `2^9`.
+    fn power(&mut self) -> Result<Expr, CellError> {
+        let base = self.unary()?;
+        if self.eat('^') {
+            let exponent = self.power()?;
+            return Ok(Expr::Binary(Op::Power, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CellError> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, CellError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('(') => {
+                self.chars.next();
+                let expr = self.expr()?;
+                if !self.eat(')') {
+                    return Err(CellError::Syntax);
+                }
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || *c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.name(),
+            _ => Err(CellError::Syntax),
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, CellError> {
+        let mut text = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            text.push(c);
+        }
+        // an exponent, as in `1e6` or `2.5E-3`
+        if let Some(e) = self.chars.next_if(|c| matches!(c, 'e' | 'E')) {
+            text.push(e);
+            if let Some(sign) = self.chars.next_if(|c| matches!(c, '+' | '-')) {
+                text.push(sign);
+            }
+            while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+                text.push(c);
+            }
+        }
+        text.parse().map(Expr::Number).map_err(|_| CellError::Syntax)
+    }
+
+    /// A function call, a cell reference or a range.
+    fn name(&mut self) -> Result<Expr, CellError> {
+        let mut letters = String::new();
+        while let Some(c) = self.chars.next_if(char::is_ascii_alphabetic) {
+            letters.push(c);
+        }
+        if self.eat('(') {
+            let function = Function::from_name(&letters).ok_or(CellError::Name)?;
+            let mut args = Vec::new();
+            if !self.eat(')') {
+                loop {
+                    args.push(self.expr()?);
+                    if self.eat(')') {
+                        break;
+                    }
+                    if !self.eat(',') {
+                        return Err(CellError::Syntax);
+                    }
+                }
+            }
+            return Ok(Expr::Call(function, args));
+        }
+        let from = self.reference(&letters)?;
+        if !self.eat(':') {
+            return Ok(Expr::Ref(from));
+        }
+        self.skip_whitespace();
+        let mut letters = String::new();
+        while let Some(c) = self.chars.next_if(char::is_ascii_alphabetic) {
+            letters.push(c);
+        }
+        Ok(Expr::Range(from, self.reference(&letters)?))
+    }
+
+    /// The reference whose column is `letters` and whose row comes next.
+    fn reference(&mut self, letters: &str) -> Result<CellRef, CellError> {
+        let mut digits = String::new();
+        while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+            digits.push(c);
+        }
+        if digits.is_empty() && !letters.is_empty() {
+            // a name that's neither a function nor a reference
+            return Err(CellError::Name);
+        }
+        if letters.is_empty() || digits.is_empty() {
+            return Err(CellError::Syntax);
+        }
+        let col = column_index(letters).ok_or(CellError::Ref)?;
+        let row = digits.parse::<usize>().map_err(|_| CellError::Ref)?;
+        Ok((row.checked_sub(1).ok_or(CellError::Ref)?, col))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROWS: usize = 10;
+    const COLS: usize = 4;
+
+    /// The cell a name like `B3` refers to.
+    fn at(name: &str) -> CellRef {
+        match Content::parse(&format!("={name}")) {
+            Content::Formula(Ok(Expr::Ref(at))) => at,
+            other => panic!("{name} isn't a reference: {other:?}"),
+        }
+    }
+
+    /// A grid evaluated the way the data grid does it: cells in a cycle show
+    /// `#CYCLE!` without being evaluated, and the others read what they refer
+    /// to.
+    struct Sheet(BTreeMap<CellRef, Content>);
+
+    impl Sheet {
+        fn new(cells: &[(&str, &str)]) -> Self {
+            Self(
+                cells
+                    .iter()
+                    .map(|(name, text)| (at(name), Content::parse(text)))
+                    .collect(),
+            )
+        }
+
+        fn value(&self, name: &str) -> Evaluated {
+            let graph = self
+                .0
+                .iter()
+                .map(|(at, content)| (*at, content.references(ROWS, COLS)))
+                .collect();
+            self.evaluate(at(name), &cyclic(&graph))
+        }
+
+        fn evaluate(&self, at: CellRef, cyclic: &BTreeSet<CellRef>) -> Evaluated {
+            if cyclic.contains(&at) {
+                return Err(CellError::Cycle);
+            }
+            match self.0.get(&at) {
+                None => Ok(Value::Blank),
+                Some(content) => content.evaluate(ROWS, COLS, &|at| self.evaluate(at, cyclic)),
+            }
+        }
+    }
+
+    fn formula(text: &str) -> Evaluated {
+        Sheet::new(&[("A1", text)]).value("A1")
+    }
+
+    #[test]
+    fn power_is_right_associative() {
+        assert_eq!(formula("=2^3^2"), Ok(Value::Number(512.0)));
+    }
+
+    #[test]
+    fn negation_binds_tighter_than_power() {
+        // as in spreadsheets, unlike in maths
+        assert_eq!(formula("=-2^2"), Ok(Value::Number(4.0)));
+        assert_eq!(formula("=0-2^2"), Ok(Value::Number(-4.0)));
+    }
+
+    #[test]
+    fn precedence_and_left_associativity() {
+        assert_eq!(formula("=1+2*3"), Ok(Value::Number(7.0)));
+        assert_eq!(formula("=(1+2)*3"), Ok(Value::Number(9.0)));
+        assert_eq!(formula("=2*3^2"), Ok(Value::Number(18.0)));
+        assert_eq!(formula("=10-4-3"), Ok(Value::Number(3.0)));
+        assert_eq!(formula("=8/4/2"), Ok(Value::Number(1.0)));
+    }
+
+    #[test]
+    fn sum_skips_blanks_and_text_in_ranges() {
+        let sheet = Sheet::new(&[
+            ("A1", "=SUM(B1:B10)"),
+            ("B1", "1"),
+            ("B3", "text"),
+            ("B4", "2.5"),
+            ("B10", "=B1*2"),
+        ]);
+        assert_eq!(sheet.value("A1"), Ok(Value::Number(5.5)));
+    }
+
+    #[test]
+    fn text_outside_a_range_is_a_value_error() {
+        let sheet = Sheet::new(&[("A1", "=SUM(B1, 2)"), ("B1", "text")]);
+        assert_eq!(sheet.value("A1"), Err(CellError::Value));
+    }
+
+    #[test]
+    fn references_outside_the_grid() {
+        assert_eq!(formula("=E1"), Err(CellError::Ref));
+        assert_eq!(formula("=A11+1"), Err(CellError::Ref));
+        assert_eq!(formula("=A0"), Err(CellError::Ref));
+        // ranges are cut to the grid
+        let sheet = Sheet::new(&[("A1", "=SUM(B9:Z99)"), ("B10", "4")]);
+        assert_eq!(sheet.value("A1"), Ok(Value::Number(4.0)));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(formula("=1/0"), Err(CellError::DivideByZero));
+        // blanks count as 0
+        assert_eq!(formula("=1/B1"), Err(CellError::DivideByZero));
+        assert_eq!(formula("=AVERAGE(B1:B3)"), Err(CellError::DivideByZero));
+    }
+
+    #[test]
+    fn a_cell_reading_itself_is_a_cycle() {
+        let sheet = Sheet::new(&[("A1", "=A1+1")]);
+        assert_eq!(sheet.value("A1"), Err(CellError::Cycle));
+        let sheet = Sheet::new(&[("A1", "=SUM(A1:A3)")]);
+        assert_eq!(sheet.value("A1"), Err(CellError::Cycle));
+    }
+
+    #[test]
+    fn two_cells_reading_each_other_are_a_cycle() {
+        let sheet = Sheet::new(&[("A1", "=B1"), ("B1", "=A1*2")]);
+        assert_eq!(sheet.value("A1"), Err(CellError::Cycle));
+        assert_eq!(sheet.value("B1"), Err(CellError::Cycle));
+    }
+
+    #[test]
+    fn a_cell_reading_a_cycle_shows_the_error_without_being_in_it() {
+        let cells = [("A1", "=B1"), ("B1", "=A1"), ("C1", "=A1+1"), ("D1", "=2")];
+        let graph = cells
+            .iter()
+            .map(|(name, text)| (at(name), Content::parse(text).references(ROWS, COLS)))
+            .collect();
+        assert_eq!(cyclic(&graph), BTreeSet::from([at("A1"), at("B1")]));
+        let sheet = Sheet::new(&cells);
+        assert_eq!(sheet.value("C1"), Err(CellError::Cycle));
+        assert_eq!(sheet.value("D1"), Ok(Value::Number(2.0)));
+    }
+}
@@ -0,0 +1,597 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use leptos::ev::{KeyboardEvent, MouseEvent};
+use leptos::*;
+use web_sys::ClipboardEvent;
+
+use crate::auth::{use_can, Capability};
+use crate::formula::{self, column_name, CellError, Content, Evaluated};
+use crate::kv::create_persisted_signal_with;
+
+/// Where the cells are kept in the backend's key-value store.
+const KV_KEY: &str = "grid.cells";
+const COLUMNS: usize = 8;
+const ROWS: usize = 30;
+
+/// Cell text by row, then column.
+type Cells = Vec<Vec<String>>;
+
+fn blank() -> Cells {
+    vec![vec![String::new(); COLUMNS]; ROWS]
+}
+
+/// Saved cells padded or cut to the grid's size.
+fn fit(mut cells: Cells) -> Cells {
+    cells.resize(ROWS, Vec::new());
+    for row in &mut cells {
+        row.resize(COLUMNS, String::new());
+    }
+    cells
+}
+
+/// Numbers in numeric order before text in case-insensitive order, with
+/// blanks last.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().is_empty(), b.trim().is_empty()) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        (false, false) => {}
+    }
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+fn show(value: &Evaluated) -> String {
+    match value {
+        Ok(value) => value.to_string(),
+        Err(err) => err.to_string(),
+    }
+}
+
+/// Rows of tab separated text, as spreadsheets copy them.
+fn parse_tsv(text: &str) -> Vec<Vec<String>> {
+    text.trim_end_matches(['\r', '\n'])
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r').split('\t').map(str::to_owned).collect())
+        .collect()
+}
+
+fn to_tsv(rows: &[Vec<String>]) -> String {
+    rows.iter()
+        .map(|row| row.join("\t"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// A row and column as shown, so the row is an index into the sorted and
+/// filtered rows rather than into the cells.
+type Position = (usize, usize);
+
+/// The cells between where a selection started and where it was extended to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Selection {
+    anchor: Position,
+    focus: Position,
+}
+
+impl Selection {
+    fn at(position: Position) -> Self {
+        Self {
+            anchor: position,
+            focus: position,
+        }
+    }
+
+    fn rows(&self) -> RangeInclusive<usize> {
+        self.anchor.0.min(self.focus.0)..=self.anchor.0.max(self.focus.0)
+    }
+
+    fn cols(&self) -> RangeInclusive<usize> {
+        self.anchor.1.min(self.focus.1)..=self.anchor.1.max(self.focus.1)
+    }
+
+    fn contains(&self, (row, col): Position) -> bool {
+        self.rows().contains(&row) && self.cols().contains(&col)
+    }
+}
+
+/// An editable grid of cells saved to the backend's key-value store.
+///
+/// The cells are free-form text and formulas rather than `DatabaseEntry2`
+/// rows, whose fixed key and number columns would leave nothing to sort,
+/// filter or paste across.
+///
+/// Every cell reads its own memo slice of the cells, as in `MemorySlices`, so
+/// editing one cell only re-renders that cell. Formulas read the memos of the
+/// cells they refer to, so the memos make up the dependency graph and only
+/// the cells that depend on an edited one are evaluated again. Sorting and
+/// filtering change which rows are shown and in what order, not the cells
+/// themselves.
+#[component]
+pub fn DataGridPage() -> impl IntoView {
+    let can_write = use_can(Capability::DataWrite);
+    let cells = create_persisted_signal_with::<Cells>(KV_KEY, blank(), fit);
+
+    let parsed = store_value(
+        (0..ROWS)
+            .map(|row| {
+                (0..COLUMNS)
+                    .map(|col| {
+                        create_memo(move |_| cells.with(|cells| Content::parse(&cells[row][col])))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>(),
+    );
+    // cells whose formulas depend on themselves; found before evaluating, as
+    // evaluating them would read their own memos
+    let cyclic = create_memo(move |_| {
+        let graph = parsed.with_value(|parsed| {
+            let mut graph = BTreeMap::new();
+            for (row, memos) in parsed.iter().enumerate() {
+                for (col, content) in memos.iter().enumerate() {
+                    let references = content.with(|content| content.references(ROWS, COLUMNS));
+                    if !references.is_empty() {
+                        graph.insert((row, col), references);
+                    }
+                }
+            }
+            graph
+        });
+        formula::cyclic(&graph)
+    });
+    // filled in below, as each cell's memo reads the memos of the cells it
+    // refers to
+    let evaluated = store_value(Vec::<Vec<Memo<Evaluated>>>::new());
+    let lookup = move |(row, col): (usize, usize)| {
+        let memo = evaluated.with_value(|memos| memos.get(row)?.get(col).copied());
+        memo.map_or(Err(CellError::Ref), |memo| memo.get())
+    };
+    evaluated.set_value(
+        (0..ROWS)
+            .map(|row| {
+                (0..COLUMNS)
+                    .map(|col| {
+                        create_memo(move |_| {
+                            if cyclic.with(|cyclic| cyclic.contains(&(row, col))) {
+                                return Err(CellError::Cycle);
+                            }
+                            let content = parsed.with_value(|parsed| parsed[row][col]);
+                            content.with(|content| content.evaluate(ROWS, COLUMNS, &lookup))
+                        })
+                    })
+                    .collect()
+            })
+            .collect(),
+    );
+    // what each cell shows
+    let values = Signal::derive(move || {
+        let memos = evaluated.with_value(Clone::clone);
+        memos
+            .iter()
+            .map(|row| row.iter().map(|memo| memo.with(show)).collect())
+            .collect::<Cells>()
+    });
+    let sort = create_rw_signal(None::<(usize, SortOrder)>);
+    let filters = create_rw_signal(vec![String::new(); COLUMNS]);
+    // indices into the cells of the rows shown, in the order shown
+    let order = create_memo(move |_| {
+        values.with(|values| {
+            let mut rows = filters.with(|filters| {
+                (0..values.len())
+                    .filter(|&row| {
+                        filters.iter().enumerate().all(|(col, filter)| {
+                            let filter = filter.trim().to_lowercase();
+                            filter.is_empty() || values[row][col].to_lowercase().contains(&filter)
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            });
+            if let Some((col, order)) = sort.get() {
+                rows.sort_by(|&a, &b| {
+                    let ordering = compare_cells(&values[a][col], &values[b][col]);
+                    match order {
+                        SortOrder::Ascending => ordering,
+                        SortOrder::Descending => ordering.reverse(),
+                    }
+                });
+            }
+            rows
+        })
+    });
+
+    let selection = create_rw_signal(Selection::at((0, 0)));
+    let editing = create_rw_signal(None::<(usize, usize)>);
+    let draft = create_rw_signal(String::new());
+    let grid_ref = create_node_ref::<html::Div>();
+
+    // the cell row shown at `row`
+    let cell_row = move |row: usize| order.with_untracked(|order| order.get(row).copied());
+    let start_editing = move |(row, col): Position, text: Option<String>| {
+        if !can_write.get_untracked() {
+            return;
+        }
+        let Some(row) = cell_row(row) else {
+            return;
+        };
+        let text = text.unwrap_or_else(|| cells.with_untracked(|cells| cells[row][col].clone()));
+        draft.set(text);
+        editing.set(Some((row, col)));
+    };
+    let commit = move || {
+        let Some((row, col)) = editing.get_untracked() else {
+            return;
+        };
+        editing.set(None);
+        let text = draft.get_untracked();
+        if cells.with_untracked(|cells| cells[row][col] != text) {
+            cells.update(|cells| cells[row][col] = text);
+        }
+        if let Some(grid) = grid_ref.get_untracked() {
+            _ = grid.focus();
+        }
+    };
+    let cancel = move || {
+        editing.set(None);
+        if let Some(grid) = grid_ref.get_untracked() {
+            _ = grid.focus();
+        }
+    };
+    // moves the focus by `by` rows and columns, extending the selection if
+    // `extend`
+    let move_by = move |(rows, cols): (isize, isize), extend: bool| {
+        let last_row = order.with_untracked(Vec::len).saturating_sub(1);
+        selection.update(|selection| {
+            let (row, col) = selection.focus;
+            let focus = (
+                row.saturating_add_signed(rows).min(last_row),
+                col.saturating_add_signed(cols).min(COLUMNS - 1),
+            );
+            *selection = if extend {
+                Selection {
+                    focus,
+                    ..*selection
+                }
+            } else {
+                Selection::at(focus)
+            };
+        });
+    };
+    // the text of the selected cells, as shown
+    let selected_cells = move || {
+        let selection = selection.get_untracked();
+        cells.with_untracked(|cells| {
+            selection
+                .rows()
+                .filter_map(cell_row)
+                .map(|row| selection.cols().map(|col| cells[row][col].clone()).collect())
+                .collect::<Vec<Vec<String>>>()
+        })
+    };
+    let clear_selected = move || {
+        let selection = selection.get_untracked();
+        let rows = selection.rows().filter_map(cell_row).collect::<Vec<_>>();
+        cells.update(|cells| {
+            for row in rows {
+                for col in selection.cols() {
+                    cells[row][col].clear();
+                }
+            }
+        });
+    };
+
+    let on_keydown = move |ev: KeyboardEvent| {
+        if editing.with_untracked(Option::is_some) {
+            return;
+        }
+        let extend = ev.shift_key();
+        match ev.key().as_str() {
+            "ArrowUp" => move_by((-1, 0), extend),
+            "ArrowDown" => move_by((1, 0), extend),
+            "ArrowLeft" => move_by((0, -1), extend),
+            "ArrowRight" => move_by((0, 1), extend),
+            "Tab" => move_by((0, if extend { -1 } else { 1 }), false),
+            "Home" => move_by((0, -(COLUMNS as isize)), extend),
+            "End" => move_by((0, COLUMNS as isize), extend),
+            "Enter" | "F2" => start_editing(selection.get_untracked().focus, None),
+            "Delete" | "Backspace" if can_write.get_untracked() => clear_selected(),
+            "Escape" => selection.update(|s| *s = Selection::at(s.focus)),
+            // typing over a cell replaces it, as in spreadsheets
+            key if key.chars().count() == 1 && !ev.ctrl_key() && !ev.meta_key() => {
+                start_editing(selection.get_untracked().focus, Some(key.to_owned()))
+            }
+            _ => return,
+        }
+        ev.prevent_default();
+    };
+    let on_copy = move |ev: ClipboardEvent| {
+        if editing.with_untracked(Option::is_some) {
+            return;
+        }
+        if let Some(clipboard) = ev.clipboard_data() {
+            _ = clipboard.set_data("text/plain", &to_tsv(&selected_cells()));
+            ev.prevent_default();
+        }
+    };
+    let on_cut = move |ev: ClipboardEvent| {
+        if editing.with_untracked(Option::is_some) || !can_write.get_untracked() {
+            return;
+        }
+        on_copy(ev);
+        clear_selected();
+    };
+    let on_paste = move |ev: ClipboardEvent| {
+        if editing.with_untracked(Option::is_some) || !can_write.get_untracked() {
+            return;
+        }
+        let Some(text) = ev.clipboard_data().and_then(|c| c.get_data("text/plain").ok()) else {
+            return;
+        };
+        ev.prevent_default();
+        let pasted = parse_tsv(&text);
+        let selection = selection.get_untracked();
+        let (top, left) = (*selection.rows().start(), *selection.cols().start());
+        // a single value fills the whole selection
+        let (height, width) = match pasted.as_slice() {
+            [row] if row.len() == 1 => (selection.rows().count(), selection.cols().count()),
+            _ => (pasted.len(), pasted.iter().map(Vec::len).max().unwrap_or(0)),
+        };
+        let targets = (top..top + height).map(cell_row).collect::<Vec<_>>();
+        cells.update(|cells| {
+            for (i, row) in targets.into_iter().enumerate() {
+                // rows past the last one shown are dropped
+                let Some(row) = row else {
+                    break;
+                };
+                for j in 0..width.min(COLUMNS - left) {
+                    let value = pasted
+                        .get(i % pasted.len())
+                        .and_then(|r| r.get(j % r.len().max(1)))
+                        .cloned()
+                        .unwrap_or_default();
+                    cells[row][left + j] = value;
+                }
+            }
+        });
+        let last_row = order.with_untracked(Vec::len).saturating_sub(1);
+        selection.set(Selection {
+            anchor: (top, left),
+            focus: (
+                (top + height).saturating_sub(1).min(last_row),
+                (left + width).min(COLUMNS) - 1,
+            ),
+        });
+    };
+
+    let toggle_sort = move |col: usize| {
+        sort.update(|sort| {
+            *sort = match *sort {
+                Some((c, SortOrder::Ascending)) if c == col => Some((col, SortOrder::Descending)),
+                Some((c, SortOrder::Descending)) if c == col => None,
+                _ => Some((col, SortOrder::Ascending)),
+            }
+        })
+    };
+
+    view! {
+        <section class="data-grid">
+            <p class="data-grid-summary">
+                {move || format!("{} of {ROWS} rows", order.with(Vec::len))}
+            </p>
+            <div
+                class="data-grid-scroll"
+                tabindex="0"
+                role="grid"
+                aria-label="Data grid"
+                node_ref=grid_ref
+                on:keydown=on_keydown
+                on:copy=on_copy
+                on:cut=on_cut
+                on:paste=on_paste
+            >
+                <table>
+                    <thead>
+                        <tr>
+                            <th></th>
+                            {(0..COLUMNS)
+                                .map(|col| {
+                                    let arrow = move || match sort.get() {
+                                        Some((c, SortOrder::Ascending)) if c == col => " ▲",
+                                        Some((c, SortOrder::Descending)) if c == col => " ▼",
+                                        _ => "",
+                                    };
+                                    view! {
+                                        <th
+                                            class="sortable"
+                                            title="Sort"
+                                            on:click=move |_| toggle_sort(col)
+                                        >
+                                            {column_name(col)}
+                                            {arrow}
+                                        </th>
+                                    }
+                                })
+                                .collect_view()}
+                        </tr>
+                        <tr class="data-grid-filters">
+                            <th></th>
+                            {(0..COLUMNS)
+                                .map(|col| {
+                                    view! {
+                                        <th>
+                                            <input
+                                                type="search"
+                                                placeholder="Filter"
+                                                aria-label=format!("Filter {}", column_name(col))
+                                                prop:value=move || {
+                                                    filters.with(|filters| filters[col].clone())
+                                                }
+                                                on:input=move |ev| {
+                                                    let value = event_target_value(&ev);
+                                                    filters.update(|filters| filters[col] = value)
+                                                }
+                                                // typing here isn't editing cells
+                                                on:keydown=|ev| ev.stop_propagation()
+                                            />
+                                        </th>
+                                    }
+                                })
+                                .collect_view()}
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <For
+                            each=move || order.get()
+                            key=|row| *row
+                            children=move |row| {
+                                // where the row is shown, which changes as it's sorted
+                                let shown_at = create_memo(move |_| {
+                                    order.with(|order| order.iter().position(|r| *r == row))
+                                });
+                                view! {
+                                    <tr>
+                                        <th>{row + 1}</th>
+                                        {(0..COLUMNS)
+                                            .map(|col| {
+                                                view! {
+                                                    <GridCell
+                                                        row
+                                                        col
+                                                        shown_at
+                                                        value=evaluated
+                                                            .with_value(|memos| memos[row][col])
+                                                        selection
+                                                        editing
+                                                        draft
+                                                        on_commit=Callback::new(move |_| commit())
+                                                        on_cancel=Callback::new(move |_| cancel())
+                                                        on_edit=Callback::new(move |position| {
+                                                            start_editing(position, None)
+                                                        })
+                                                        on_move=Callback::new(move |step| {
+                                                            move_by(step, false)
+                                                        })
+                                                    />
+                                                }
+                                            })
+                                            .collect_view()}
+                                    </tr>
+                                }
+                            }
+                        />
+
+                    </tbody>
+                </table>
+            </div>
+        </section>
+    }
+}
+
+#[component]
+fn GridCell(
+    /// Index into the cells.
+    row: usize,
+    col: usize,
+    /// Where the row is shown, if it is.
+    shown_at: Memo<Option<usize>>,
+    value: Memo<Evaluated>,
+    selection: RwSignal<Selection>,
+    /// The cell being edited, as indices into the cells.
+    editing: RwSignal<Option<(usize, usize)>>,
+    draft: RwSignal<String>,
+    on_commit: Callback<()>,
+    on_cancel: Callback<()>,
+    on_edit: Callback<Position>,
+    /// Moves the selection by rows and columns.
+    on_move: Callback<(isize, isize)>,
+) -> impl IntoView {
+    let is_editing = create_memo(move |_| editing.get() == Some((row, col)));
+    let selected = move || {
+        shown_at
+            .get()
+            .is_some_and(|shown| selection.with(|s| s.contains((shown, col))))
+    };
+    let focused = move || {
+        shown_at
+            .get()
+            .is_some_and(|shown| selection.with(|s| s.focus == (shown, col)))
+    };
+    let on_mousedown = move |ev: MouseEvent| {
+        let Some(shown) = shown_at.get_untracked() else {
+            return;
+        };
+        if ev.shift_key() {
+            selection.update(|s| s.focus = (shown, col));
+        } else {
+            selection.set(Selection::at((shown, col)));
+        }
+    };
+    // dragging across cells selects them
+    let on_mouseenter = move |ev: MouseEvent| {
+        if ev.buttons() & 1 == 0 {
+            return;
+        }
+        if let Some(shown) = shown_at.get_untracked() {
+            selection.update(|s| s.focus = (shown, col));
+        }
+    };
+    let input_ref = create_node_ref::<html::Input>();
+    create_effect(move |_| {
+        if let Some(input) = input_ref.get() {
+            _ = input.focus();
+        }
+    });
+
+    view! {
+        <td
+            class:selected=selected
+            class:focused=focused
+            class:numeric=move || value.with(|value| matches!(value, Ok(formula::Value::Number(_))))
+            class:error=move || value.with(Result::is_err)
+            on:mousedown=on_mousedown
+            on:mouseenter=on_mouseenter
+            on:dblclick=move |_| {
+                if let Some(shown) = shown_at.get_untracked() {
+                    on_edit.call((shown, col));
+                }
+            }
+        >
+            <Show when=move || is_editing.get() fallback=move || value.with(show)>
+                <input
+                    type="text"
+                    node_ref=input_ref
+                    prop:value=draft
+                    on:input=move |ev| draft.set(event_target_value(&ev))
+                    on:keydown=move |ev: KeyboardEvent| {
+                        ev.stop_propagation();
+                        match ev.key().as_str() {
+                            "Enter" | "Tab" => {
+                                ev.prevent_default();
+                                on_commit.call(());
+                                let step = if ev.key() == "Tab" { (0, 1) } else { (1, 0) };
+                                let step = if ev.shift_key() { (-step.0, -step.1) } else { step };
+                                on_move.call(step);
+                            }
+                            "Escape" => on_cancel.call(()),
+                            _ => {}
+                        }
+                    }
+                    on:blur=move |_| on_commit.call(())
+                />
+            </Show>
+        </td>
+    }
+}
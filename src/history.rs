@@ -0,0 +1,102 @@
+use std::rc::Rc;
+
+use leptos::*;
+
+/// How many changes can be undone.
+const MAX_HISTORY: usize = 50;
+
+#[derive(Clone)]
+struct Entry {
+    id: u64,
+    undo: Rc<dyn Fn()>,
+    redo: Rc<dyn Fn()>,
+}
+
+/// Undo and redo stacks for changes made in this window.
+///
+/// Each change is recorded with a way to revert it and a way to make it
+/// again; making a new change clears the redo stack.
+#[derive(Clone, Copy)]
+pub struct History {
+    undo: RwSignal<Vec<Entry>>,
+    redo: RwSignal<Vec<Entry>>,
+    next_id: StoredValue<u64>,
+}
+
+pub fn provide_history() {
+    provide_context(History {
+        undo: create_rw_signal(Vec::new()),
+        redo: create_rw_signal(Vec::new()),
+        next_id: store_value(0),
+    });
+}
+
+pub fn use_history() -> History {
+    expect_context::<History>()
+}
+
+impl History {
+    /// Records a change that was just made, and returns its id.
+    pub fn push(&self, undo: impl Fn() + 'static, redo: impl Fn() + 'static) -> u64 {
+        let id = self.next_id.get_value();
+        self.next_id.set_value(id + 1);
+        self.undo.update(|stack| {
+            stack.push(Entry {
+                id,
+                undo: Rc::new(undo),
+                redo: Rc::new(redo),
+            });
+            let excess = stack.len().saturating_sub(MAX_HISTORY);
+            stack.drain(..excess);
+        });
+        self.redo.update(Vec::clear);
+        id
+    }
+
+    pub fn can_undo(&self) -> Signal<bool> {
+        let undo = self.undo;
+        Signal::derive(move || undo.with(|stack| !stack.is_empty()))
+    }
+
+    pub fn can_redo(&self) -> Signal<bool> {
+        let redo = self.redo;
+        Signal::derive(move || redo.with(|stack| !stack.is_empty()))
+    }
+
+    pub fn undo(&self) {
+        if let Some(entry) = self.undo.try_update(Vec::pop).flatten() {
+            (entry.undo)();
+            self.redo.update(|stack| stack.push(entry));
+        }
+    }
+
+    pub fn redo(&self) {
+        if let Some(entry) = self.redo.try_update(Vec::pop).flatten() {
+            (entry.redo)();
+            self.undo.update(|stack| stack.push(entry));
+        }
+    }
+
+    /// Reverts one particular change, e.g. from its toast's Undo button, even
+    /// if later changes were made since. Does nothing if it was already
+    /// undone.
+    pub fn undo_entry(&self, id: u64) {
+        let is_last = self
+            .undo
+            .with_untracked(|stack| stack.last().map(|entry| entry.id) == Some(id));
+        if is_last {
+            return self.undo();
+        }
+        // later changes may build on it, so it can't be redone out of order
+        let entry = self
+            .undo
+            .try_update(|stack| {
+                let index = stack.iter().position(|entry| entry.id == id)?;
+                Some(stack.remove(index))
+            })
+            .flatten();
+        if let Some(entry) = entry {
+            (entry.undo)();
+        }
+    }
+}
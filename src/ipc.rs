@@ -0,0 +1,176 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use leptos::{on_cleanup, spawn_local};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "tauri"], catch)]
+    async fn invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsValue>;
+
+    /// Resolves to the function that removes the listener.
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"], js_name = listen)]
+    fn tauri_listen(event: &str, handler: &Closure<dyn FnMut(JsValue)>) -> js_sys::Promise;
+}
+
+/// Mirrors the backend's error kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ErrorKind {
+    Forbidden,
+    NotFound,
+    Invalid,
+    Cancelled,
+    /// The backend turned the request away without doing any work.
+    Busy,
+    /// No response arrived in time. Raised by the UI, never by the backend.
+    Timeout,
+    Internal,
+}
+
+/// An error returned by a command, or raised while talking to the backend.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IpcError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl IpcError {
+    pub(crate) fn cancelled() -> Self {
+        Self {
+            kind: ErrorKind::Cancelled,
+            message: "request was cancelled".into(),
+        }
+    }
+
+    pub(crate) fn timeout(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::Timeout,
+            message: message.into(),
+        }
+    }
+
+    fn internal(message: impl Into<String>) -> Self {
+        Self {
+            kind: ErrorKind::Internal,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_forbidden(&self) -> bool {
+        self.kind == ErrorKind::Forbidden
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.kind == ErrorKind::Cancelled
+    }
+}
+
+impl std::fmt::Display for IpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ErrorKind::Forbidden => write!(f, "Not allowed in this window: {}", self.message),
+            ErrorKind::NotFound => write!(f, "Not found: {}", self.message),
+            ErrorKind::Invalid => write!(f, "Invalid: {}", self.message),
+            ErrorKind::Cancelled => write!(f, "Cancelled: {}", self.message),
+            ErrorKind::Busy => write!(f, "Busy, try again later: {}", self.message),
+            ErrorKind::Timeout => write!(f, "Timed out: {}", self.message),
+            ErrorKind::Internal => write!(f, "Something went wrong: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for IpcError {}
+
+/// Calls a backend command and decodes its result.
+///
+/// Errors the backend didn't produce itself (an unknown command, a panic, a
+/// plain string rejection) come back as [`ErrorKind::Internal`].
+pub async fn call<A, R>(cmd: &str, args: &A) -> Result<R, IpcError>
+where
+    A: Serialize + ?Sized,
+    R: DeserializeOwned,
+{
+    let args = serde_wasm_bindgen::to_value(args).map_err(|e| IpcError::internal(e.to_string()))?;
+    match invoke(cmd, args).await {
+        Ok(value) => {
+            serde_wasm_bindgen::from_value(value).map_err(|e| IpcError::internal(e.to_string()))
+        }
+        Err(err) => Err(serde_wasm_bindgen::from_value(err.clone()).unwrap_or_else(|_| {
+            IpcError::internal(err.as_string().unwrap_or_else(|| format!("{err:?}")))
+        })),
+    }
+}
+
+/// For commands that take no arguments.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoArgs {}
+
+#[derive(Default)]
+struct Listener {
+    handler: Option<Closure<dyn FnMut(JsValue)>>,
+    unlisten: Option<js_sys::Function>,
+    disposed: bool,
+}
+
+impl Listener {
+    /// The handler is only dropped once the backend has stopped calling it.
+    fn dispose(&mut self) {
+        self.disposed = true;
+        if let Some(unlisten) = self.unlisten.take() {
+            _ = unlisten.call0(&JsValue::NULL);
+            self.handler = None;
+        }
+    }
+}
+
+/// Calls `handler` with the payload of every backend `event` until the
+/// current reactive owner is disposed.
+///
+/// Payloads that don't decode as `T` are logged and dropped.
+pub fn listen<T, F>(event: &str, handler: F)
+where
+    T: DeserializeOwned,
+    F: Fn(T) + 'static,
+{
+    let name = event.to_owned();
+    let closure = Closure::<dyn FnMut(JsValue)>::new(move |raw: JsValue| {
+        let payload = js_sys::Reflect::get(&raw, &"payload".into()).unwrap_or(JsValue::NULL);
+        match serde_wasm_bindgen::from_value(payload) {
+            Ok(payload) => handler(payload),
+            Err(err) => log::warn!("dropping malformed `{name}` payload: {err}"),
+        }
+    });
+    let listener = Rc::new(RefCell::new(Listener {
+        handler: Some(closure),
+        ..Default::default()
+    }));
+
+    on_cleanup({
+        let listener = Rc::clone(&listener);
+        move || listener.borrow_mut().dispose()
+    });
+
+    let event = event.to_owned();
+    spawn_local(async move {
+        let registered = {
+            let listener = listener.borrow();
+            let Some(handler) = listener.handler.as_ref() else {
+                return;
+            };
+            tauri_listen(&event, handler)
+        };
+        let unlisten = wasm_bindgen_futures::JsFuture::from(registered).await;
+        let mut listener = listener.borrow_mut();
+        match unlisten {
+            Ok(unlisten) => listener.unlisten = unlisten.dyn_into().ok(),
+            Err(err) => log::warn!("failed to listen to `{event}`: {err:?}"),
+        }
+        if listener.disposed {
+            // the owner went away while we were registering
+            listener.dispose();
+        }
+    });
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+
+use futures::channel::oneshot;
+use leptos::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{use_can, Capability};
+use crate::cancel::CancelToken;
+use crate::ipc::{self, IpcError, NoArgs};
+use crate::query::create_query;
+
+/// How many finished jobs the job list keeps.
+const MAX_FINISHED: usize = 20;
+
+/// Mirrors the backend's job kinds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "args", rename_all = "snake_case")]
+pub enum JobSpec {
+    LoadData { value: i32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running { progress: f32 },
+    Completed { result: serde_json::Value },
+    Failed { error: String },
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Completed { .. } | JobState::Failed { .. } | JobState::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct JobInfo {
+    pub id: Uuid,
+    pub kind: String,
+    pub state: JobState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobError {
+    /// The job couldn't be started or tracked.
+    Ipc(IpcError),
+    Failed(String),
+    Cancelled,
+    /// The job finished but its result wasn't the expected type.
+    Decode(String),
+}
+
+impl std::fmt::Display for JobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobError::Ipc(err) => err.fmt(f),
+            JobError::Failed(error) => write!(f, "Job failed: {error}"),
+            JobError::Cancelled => write!(f, "Job was cancelled"),
+            JobError::Decode(error) => write!(f, "Unexpected job result: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+impl From<IpcError> for JobError {
+    fn from(err: IpcError) -> Self {
+        JobError::Ipc(err)
+    }
+}
+
+#[derive(Serialize)]
+struct StartJobArgs<'a> {
+    spec: &'a JobSpec,
+}
+
+#[derive(Serialize)]
+struct CancelJobArgs {
+    id: Uuid,
+}
+
+/// Tracks backend jobs from their `job://*` events.
+#[derive(Clone, Copy)]
+pub struct Jobs {
+    jobs: RwSignal<Vec<JobInfo>>,
+    waiters: StoredValue<HashMap<Uuid, Vec<oneshot::Sender<JobState>>>>,
+}
+
+pub fn provide_jobs() {
+    let jobs = Jobs {
+        jobs: create_rw_signal(Vec::new()),
+        waiters: store_value(HashMap::new()),
+    };
+    for event in ["job://progress", "job://completed", "job://failed", "job://cancelled"] {
+        ipc::listen(event, move |info: JobInfo| jobs.record(info));
+    }
+    spawn_local(async move {
+        // pick up jobs started before this window loaded
+        if let Ok(existing) = ipc::call::<_, Vec<JobInfo>>("list_jobs", &NoArgs {}).await {
+            for info in existing {
+                if jobs.jobs.with_untracked(|jobs| jobs.iter().all(|job| job.id != info.id)) {
+                    jobs.record(info);
+                }
+            }
+        }
+    });
+    provide_context(jobs);
+}
+
+pub fn use_jobs() -> Jobs {
+    expect_context::<Jobs>()
+}
+
+impl Jobs {
+    fn record(&self, info: JobInfo) {
+        if info.state.is_finished() {
+            let waiting = self
+                .waiters
+                .try_update_value(|waiters| waiters.remove(&info.id))
+                .flatten();
+            for waiter in waiting.into_iter().flatten() {
+                _ = waiter.send(info.state.clone());
+            }
+        }
+        self.jobs.update(|jobs| {
+            match jobs.iter_mut().find(|job| job.id == info.id) {
+                Some(job) => *job = info,
+                None => jobs.push(info),
+            }
+            let finished = jobs.iter().filter(|job| job.state.is_finished()).count();
+            let mut excess = finished.saturating_sub(MAX_FINISHED);
+            jobs.retain(|job| {
+                if excess > 0 && job.state.is_finished() {
+                    excess -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        });
+    }
+
+    pub fn list(&self) -> Signal<Vec<JobInfo>> {
+        self.jobs.into()
+    }
+
+    pub async fn start(&self, spec: &JobSpec) -> Result<Uuid, IpcError> {
+        ipc::call("start_job", &StartJobArgs { spec }).await
+    }
+
+    pub async fn cancel(&self, id: Uuid) -> Result<(), IpcError> {
+        ipc::call("cancel_job", &CancelJobArgs { id }).await
+    }
+
+    /// Resolves with the job's final state.
+    pub async fn wait(&self, id: Uuid) -> JobState {
+        let finished = self.jobs.with_untracked(|jobs| {
+            jobs.iter()
+                .find(|job| job.id == id && job.state.is_finished())
+                .map(|job| job.state.clone())
+        });
+        if let Some(state) = finished {
+            return state;
+        }
+        let (tx, rx) = oneshot::channel();
+        self.waiters
+            .update_value(|waiters| waiters.entry(id).or_default().push(tx));
+        // the sender is only dropped without sending if the window is torn down
+        rx.await.unwrap_or(JobState::Cancelled)
+    }
+
+    /// Starts a job and waits for its result. Cancelling `token` cancels the
+    /// job.
+    pub async fn run<R: DeserializeOwned>(
+        &self,
+        spec: &JobSpec,
+        token: &CancelToken,
+    ) -> Result<R, JobError> {
+        let id = self.start(spec).await?;
+        let jobs = *self;
+        token.on_cancel(move || {
+            spawn_local(async move {
+                if let Err(err) = jobs.cancel(id).await {
+                    log::warn!("failed to cancel job {id}: {err}");
+                }
+            })
+        });
+        match self.wait(id).await {
+            JobState::Completed { result } => {
+                serde_json::from_value(result).map_err(|err| JobError::Decode(err.to_string()))
+            }
+            JobState::Failed { error } => Err(JobError::Failed(error)),
+            _ => Err(JobError::Cancelled),
+        }
+    }
+}
+
+/// A cached query whose fetches run as backend jobs.
+///
+/// Useful inside `<Suspense/>`: the resource stays pending until the job
+/// completes, fails or is cancelled. Results are cached per [`JobSpec`], so
+/// going back to a spec shows its last result while a new job refreshes it.
+/// A job is cancelled once no resource waits for its result.
+pub fn create_job_resource<S, R>(
+    source: impl Fn() -> S + 'static,
+    spec: impl Fn(S) -> JobSpec + 'static,
+) -> Resource<(JobSpec, u64), Result<R, JobError>>
+where
+    R: DeserializeOwned + Clone + 'static,
+{
+    let jobs = use_jobs();
+    create_query(
+        "job",
+        move || spec(source()),
+        // a job nobody waits for any more is cancelled along with its fetch
+        move |spec, token| async move { jobs.run(&spec, &token).await },
+    )
+}
+
+#[component]
+pub fn JobList() -> impl IntoView {
+    let jobs = use_jobs();
+    let can_cancel = use_can(Capability::JobRun);
+    view! {
+        <h2>"Jobs"</h2>
+        <ul class="jobs">
+            <For
+                each=move || jobs.list().get()
+                key=|job| (job.id, format!("{:?}", job.state))
+                children=move |job| {
+                    let id = job.id;
+                    let status = match &job.state {
+                        JobState::Queued => view! { "queued" }.into_view(),
+                        JobState::Running { progress } => {
+                            view! { <progress max="1" value=*progress></progress> }.into_view()
+                        }
+                        JobState::Completed { result } => format!("done: {result}").into_view(),
+                        JobState::Failed { error } => {
+                            view! { <span class="error">{error.clone()}</span> }.into_view()
+                        }
+                        JobState::Cancelled => view! { "cancelled" }.into_view(),
+                    };
+                    let finished = job.state.is_finished();
+                    view! {
+                        <li>
+                            <code>{job.kind}</code>
+                            " "
+                            {status}
+                            <Show when=move || !finished && can_cancel.get()>
+                                <button on:click=move |_| {
+                                    spawn_local(async move {
+                                        if let Err(err) = jobs.cancel(id).await {
+                                            log::warn!("failed to cancel job {id}: {err}");
+                                        }
+                                    })
+                                }>"Cancel"</button>
+                            </Show>
+                        </li>
+                    }
+                }
+            />
+
+        </ul>
+    }
+}
@@ -0,0 +1,506 @@
+use leptos::ev::DragEvent;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{use_can, Capability};
+use crate::history::use_history;
+use crate::modal::{use_modals, ModalHandle};
+use crate::outbox::{use_outbox, Mutation};
+use crate::settings::use_settings;
+use crate::toast::{use_toasts, ToastLevel};
+use crate::todos::{create_todos, format_due, queue_undoable, Status, Todo};
+
+/// Mirrors the backend's column filters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "by", rename_all = "snake_case")]
+pub enum ColumnFilter {
+    Status { status: Status },
+    Tag { tag: String },
+}
+
+impl ColumnFilter {
+    fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            ColumnFilter::Status { status } => todo.status() == *status,
+            ColumnFilter::Tag { tag } => todo.details.tags.contains(tag),
+        }
+    }
+}
+
+/// Mirrors the backend's board columns.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KanbanColumn {
+    pub title: String,
+    pub filter: ColumnFilter,
+    #[serde(default)]
+    pub wip_limit: Option<u32>,
+}
+
+/// One column per status, used until columns are configured.
+fn default_columns() -> Vec<KanbanColumn> {
+    Status::ALL
+        .into_iter()
+        .map(|status| KanbanColumn {
+            title: status.label().to_owned(),
+            filter: ColumnFilter::Status { status },
+            wip_limit: None,
+        })
+        .collect()
+}
+
+/// The change moving `todo` out of the `from` column into the `to` one, and
+/// its inverse; `None` if it's in `to` already.
+fn move_between(
+    todo: &Todo,
+    from: &ColumnFilter,
+    to: &ColumnFilter,
+) -> Option<(Mutation, Mutation)> {
+    if to.matches(todo) {
+        return None;
+    }
+    let id = todo.id;
+    match to {
+        ColumnFilter::Status { status } => {
+            // the id the next occurrence gets, if this completes a recurring todo
+            let next_id = Uuid::new_v4();
+            let change = Mutation::SetTodoStatus {
+                id,
+                status: *status,
+                next_id,
+            };
+            let inverse = Mutation::SetTodoStatus {
+                id,
+                status: todo.status(),
+                next_id,
+            };
+            Some((change, inverse))
+        }
+        ColumnFilter::Tag { tag } => {
+            let mut details = todo.details.clone();
+            if let ColumnFilter::Tag { tag: old } = from {
+                details.tags.retain(|t| t != old);
+            }
+            details.tags.push(tag.clone());
+            let change = Mutation::UpdateTodo {
+                id,
+                text: todo.text.clone(),
+                details,
+            };
+            let inverse = Mutation::UpdateTodo {
+                id,
+                text: todo.text.clone(),
+                details: todo.details.clone(),
+            };
+            Some((change, inverse))
+        }
+    }
+}
+
+/// The todo being dragged, and the index of the column it came from.
+type Dragging = RwSignal<Option<(Uuid, usize)>>;
+
+/// Todos in columns by status or tag. Dragging a card to another column
+/// queues the change through the outbox, unless that column is at its WIP
+/// limit.
+#[component]
+pub fn KanbanPage() -> impl IntoView {
+    let settings = use_settings();
+    let saved_columns = settings.get();
+    let columns = create_memo(move |_| {
+        let columns = saved_columns.with(|s| s.kanban_columns.clone());
+        if columns.is_empty() {
+            default_columns()
+        } else {
+            columns
+        }
+    });
+    let (loaded, todos) = create_todos();
+    let dragging: Dragging = create_rw_signal(None);
+    let outbox = use_outbox();
+    let history = use_history();
+    let toasts = use_toasts();
+    let modals = use_modals();
+    let can_configure = use_can(Capability::SettingsWrite);
+
+    let drop_on = Callback::new(move |to: usize| {
+        let Some((id, from)) = dragging.get_untracked() else {
+            return;
+        };
+        dragging.set(None);
+        let columns = columns.get_untracked();
+        let (Some(from), Some(to)) = (columns.get(from), columns.get(to)) else {
+            return;
+        };
+        let todos = todos.get_untracked();
+        let Some(todo) = todos.iter().find(|todo| todo.id == id) else {
+            return;
+        };
+        let Some((change, inverse)) = move_between(todo, &from.filter, &to.filter) else {
+            return;
+        };
+        if let Some(limit) = to.wip_limit {
+            let count = todos.iter().filter(|todo| to.filter.matches(todo)).count();
+            if count >= limit as usize {
+                let message = format!("{} is at its limit of {limit}", to.title);
+                toasts.notify(ToastLevel::Warning, message, None);
+                return;
+            }
+        }
+        queue_undoable(outbox, history, toasts, ToastLevel::Success, "Todo moved", change, inverse);
+    });
+    let configure = move |_| {
+        let current = columns.get_untracked();
+        modals.open(move |handle| view! { <ColumnEditor columns=current.clone() handle/> }, || {});
+    };
+
+    view! {
+        <section class="kanban" class:dragging=move || dragging.with(Option::is_some)>
+            <header>
+                <h2>"Board"</h2>
+                <button disabled=move || !can_configure.get() on:click=configure>
+                    "Columns..."
+                </button>
+            </header>
+            {move || {
+                loaded
+                    .get()
+                    .and_then(Result::err)
+                    .map(|err| {
+                        view! { <p class="error">{format!("Couldn't load todos: {err}")}</p> }
+                    })
+            }}
+            <div class="kanban-columns">
+                <For
+                    each=move || columns.get().into_iter().enumerate()
+                    key=|column| column.clone()
+                    children=move |(index, column)| {
+                        view! { <BoardColumn index column todos dragging on_drop=drop_on/> }
+                    }
+                />
+
+            </div>
+        </section>
+    }
+}
+
+#[component]
+fn BoardColumn(
+    index: usize,
+    column: KanbanColumn,
+    todos: Signal<Vec<Todo>>,
+    dragging: Dragging,
+    on_drop: Callback<usize>,
+) -> impl IntoView {
+    let KanbanColumn {
+        title,
+        filter,
+        wip_limit,
+    } = column;
+    // only the ids, so cards are added and removed without re-rendering the
+    // others; each card follows its own todo, like DynamicList's counters
+    let ids = create_memo(move |_| {
+        todos.with(|todos| {
+            todos
+                .iter()
+                .filter(|todo| filter.matches(todo))
+                .map(|todo| todo.id)
+                .collect::<Vec<_>>()
+        })
+    });
+    let count = move || ids.with(Vec::len);
+    let over_limit = move || wip_limit.is_some_and(|limit| count() > limit as usize);
+    let at_limit = move || wip_limit.is_some_and(|limit| count() >= limit as usize);
+
+    view! {
+        <section
+            class="kanban-column"
+            class:at-limit=at_limit
+            class:over-limit=over_limit
+            on:dragover=move |ev: DragEvent| {
+                if dragging.with(Option::is_some) {
+                    ev.prevent_default();
+                }
+            }
+            on:drop=move |ev: DragEvent| {
+                ev.prevent_default();
+                on_drop.call(index);
+            }
+        >
+            <h3>
+                {title} " "
+                <span class="kanban-count">
+                    {count}
+                    {wip_limit.map(|limit| format!(" / {limit}"))}
+                </span>
+            </h3>
+            <ul>
+                <For
+                    each=move || ids.get()
+                    key=|id| *id
+                    children=move |id| {
+                        let todo = create_memo(move |_| {
+                            todos.with(|todos| todos.iter().find(|todo| todo.id == id).cloned())
+                        });
+                        view! { <Card id todo column=index dragging/> }
+                    }
+                />
+
+            </ul>
+        </section>
+    }
+}
+
+#[component]
+fn Card(id: Uuid, todo: Memo<Option<Todo>>, column: usize, dragging: Dragging) -> impl IntoView {
+    let can_write = use_can(Capability::TodoWrite);
+    let start = move |ev: DragEvent| {
+        // some webviews won't start a drag without data
+        if let Some(data) = ev.data_transfer() {
+            _ = data.set_data("text/plain", &id.to_string());
+            data.set_effect_allowed("move");
+        }
+        dragging.set(Some((id, column)));
+    };
+    let field = move |get: fn(&Todo) -> View| move || todo.with(|todo| todo.as_ref().map(get));
+
+    view! {
+        <li
+            class="kanban-card"
+            class:pending=move || todo.with(|todo| todo.as_ref().is_some_and(|t| t.pending))
+            class:done=move || todo.with(|todo| todo.as_ref().is_some_and(|t| t.done))
+            draggable=move || if can_write.get() { "true" } else { "false" }
+            on:dragstart=start
+            on:dragend=move |_| dragging.set(None)
+        >
+            {field(|todo| todo.text.clone().into_view())}
+            {field(|todo| {
+                todo.details
+                    .due
+                    .map(|due| view! { <span class="todo-due">{format_due(due)}</span> })
+                    .into_view()
+            })}
+            {field(|todo| {
+                todo.details
+                    .tags
+                    .iter()
+                    .map(|tag| view! { <span class="todo-tag">"#" {tag.clone()}</span> })
+                    .collect_view()
+            })}
+        </li>
+    }
+}
+
+/// Edits the board's columns and saves them to the settings.
+#[component]
+fn ColumnEditor(columns: Vec<KanbanColumn>, handle: ModalHandle) -> impl IntoView {
+    let settings = use_settings();
+    // keyed rows with a signal each, as in DynamicList
+    let next_id = store_value(0usize);
+    let new_row = move |column: KanbanColumn| {
+        let id = next_id.get_value();
+        next_id.set_value(id + 1);
+        (id, create_rw_signal(column))
+    };
+    let rows = create_rw_signal(columns.into_iter().map(new_row).collect::<Vec<_>>());
+    let set_rows = move |columns: Vec<KanbanColumn>| {
+        rows.update(|rows| {
+            for (_, row) in rows.drain(..) {
+                row.dispose();
+            }
+            rows.extend(columns.into_iter().map(new_row));
+        })
+    };
+    let remove = move |id: usize| {
+        rows.update(|rows| {
+            rows.retain(|(row_id, row)| {
+                if *row_id == id {
+                    row.dispose();
+                }
+                *row_id != id
+            })
+        })
+    };
+    // moves the row one place left, or right if `by` is 1
+    let shift = move |id: usize, by: isize| {
+        rows.update(|rows| {
+            let Some(from) = rows.iter().position(|(row_id, _)| *row_id == id) else {
+                return;
+            };
+            let to = from.saturating_add_signed(by).min(rows.len() - 1);
+            rows.swap(from, to);
+        })
+    };
+    let add = move |_| {
+        rows.update(|rows| {
+            rows.push(new_row(KanbanColumn {
+                title: "New column".to_owned(),
+                filter: ColumnFilter::Status {
+                    status: Status::Open,
+                },
+                wip_limit: None,
+            }))
+        })
+    };
+    // a tag column without a tag would never hold anything
+    let valid = move || {
+        rows.with(|rows| {
+            rows.iter().all(|(_, row)| {
+                row.with(|column| match &column.filter {
+                    ColumnFilter::Tag { tag } => !tag.trim().is_empty(),
+                    ColumnFilter::Status { .. } => true,
+                })
+            })
+        })
+    };
+    let save = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let columns = rows.with_untracked(|rows| {
+            rows.iter()
+                .map(|(_, row)| row.get_untracked())
+                .collect::<Vec<_>>()
+        });
+        settings.update(|settings| settings.kanban_columns = columns);
+        handle.close();
+    };
+
+    view! {
+        <form class="column-editor" on:submit=save>
+            <h2>"Board columns"</h2>
+            <ul>
+                <For
+                    each=move || rows.get()
+                    key=|(id, _)| *id
+                    children=move |(id, column)| {
+                        view! {
+                            <li>
+                                <ColumnFields column/>
+                                <button
+                                    type="button"
+                                    aria-label="Move left"
+                                    on:click=move |_| shift(id, -1)
+                                >
+                                    "←"
+                                </button>
+                                <button
+                                    type="button"
+                                    aria-label="Move right"
+                                    on:click=move |_| shift(id, 1)
+                                >
+                                    "→"
+                                </button>
+                                <button type="button" on:click=move |_| remove(id)>
+                                    "Remove"
+                                </button>
+                            </li>
+                        }
+                    }
+                />
+
+            </ul>
+            <div class="modal-buttons">
+                <button type="button" on:click=add>
+                    "Add column"
+                </button>
+                <button type="button" on:click=move |_| set_rows(default_columns())>
+                    "Reset"
+                </button>
+                <button type="button" on:click=move |_| handle.close()>
+                    "Cancel"
+                </button>
+                <button type="submit" disabled=move || !valid()>
+                    "Save"
+                </button>
+            </div>
+        </form>
+    }
+}
+
+#[component]
+fn ColumnFields(column: RwSignal<KanbanColumn>) -> impl IntoView {
+    let is_tag = move || column.with(|c| matches!(c.filter, ColumnFilter::Tag { .. }));
+    let status = move || match column.with(|c| c.filter.clone()) {
+        ColumnFilter::Status { status } => Some(status),
+        ColumnFilter::Tag { .. } => None,
+    };
+    let tag = move || match column.with(|c| c.filter.clone()) {
+        ColumnFilter::Tag { tag } => tag,
+        ColumnFilter::Status { .. } => String::new(),
+    };
+
+    view! {
+        <input
+            type="text"
+            aria-label="Title"
+            prop:value=move || column.with(|c| c.title.clone())
+            on:input=move |ev| column.update(|c| c.title = event_target_value(&ev))
+        />
+        <select
+            aria-label="Holds"
+            on:change=move |ev| {
+                let filter = match event_target_value(&ev).as_str() {
+                    "tag" => ColumnFilter::Tag { tag: String::new() },
+                    _ => ColumnFilter::Status { status: Status::Open },
+                };
+                column.update(|c| c.filter = filter)
+            }
+        >
+            <option value="status" selected=move || !is_tag()>
+                "Status"
+            </option>
+            <option value="tag" selected=is_tag>
+                "Tag"
+            </option>
+        </select>
+        <Show
+            when=is_tag
+            fallback=move || {
+                view! {
+                    <select
+                        aria-label="Status"
+                        on:change=move |ev| {
+                            if let Some(status) = Status::from_name(&event_target_value(&ev)) {
+                                column.update(|c| c.filter = ColumnFilter::Status { status });
+                            }
+                        }
+                    >
+                        {Status::ALL
+                            .into_iter()
+                            .map(|s| {
+                                view! {
+                                    <option value=s.name() selected=move || status() == Some(s)>
+                                        {s.label()}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                }
+            }
+        >
+            <input
+                type="text"
+                aria-label="Tag"
+                placeholder="tag"
+                prop:value=tag
+                on:input=move |ev| {
+                    let tag = event_target_value(&ev).trim().trim_start_matches('#').to_owned();
+                    column.update(|c| c.filter = ColumnFilter::Tag { tag })
+                }
+            />
+        </Show>
+        <input
+            type="number"
+            min="1"
+            aria-label="WIP limit"
+            placeholder="No limit"
+            prop:value=move || {
+                column.with(|c| c.wip_limit.map(|l| l.to_string()).unwrap_or_default())
+            }
+            on:input=move |ev| {
+                let limit = event_target_value(&ev).parse::<u32>().ok().filter(|l| *l > 0);
+                column.update(|c| c.wip_limit = limit)
+            }
+        />
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use leptos::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::auth::{use_can, Capability};
+use crate::debounce::create_debounced;
+use crate::ipc::{self, IpcError};
+use crate::query::create_command_query;
+
+/// How long a persisted signal has to stop changing before it's saved.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GetArgs {
+    key: &'static str,
+}
+
+#[derive(Serialize)]
+struct SetArgs<'a, T> {
+    key: &'a str,
+    value: &'a T,
+}
+
+/// The JSON saved under `key` in the backend's key-value store, `None` if it
+/// was never set. Refetched whenever any window changes it.
+pub fn create_kv_query(
+    key: &'static str,
+) -> Resource<(GetArgs, u64), Result<Option<Value>, IpcError>> {
+    create_command_query("kv_get", move || GetArgs { key })
+}
+
+/// Saves `value` under `key`.
+pub async fn save<T: Serialize>(key: &str, value: &T) -> Result<(), IpcError> {
+    ipc::call("kv_set", &SetArgs { key, value }).await
+}
+
+/// A signal saved under `key` in the backend's key-value store, so it
+/// survives reloads and follows changes made in other windows.
+///
+/// It starts at `default` and takes the saved value once that loads. A saved
+/// value that no longer deserializes as `T`, say after a field was renamed,
+/// is ignored with a warning and the signal keeps `default`. Changes are
+/// saved once they've settled, unless the session can't write data.
+pub fn create_persisted_signal<T>(key: &'static str, default: T) -> RwSignal<T>
+where
+    T: Serialize + DeserializeOwned + Clone + PartialEq + 'static,
+{
+    create_persisted_signal_with(key, default, |value| value)
+}
+
+/// [`create_persisted_signal`], passing the saved value through `on_load`
+/// first, say to bring it in line with what the signal is expected to hold.
+pub fn create_persisted_signal_with<T>(
+    key: &'static str,
+    default: T,
+    on_load: impl Fn(T) -> T + 'static,
+) -> RwSignal<T>
+where
+    T: Serialize + DeserializeOwned + Clone + PartialEq + 'static,
+{
+    let can_write = use_can(Capability::DataWrite);
+    let signal = create_rw_signal(default.clone());
+    // what the backend has, so only local changes are saved and a change from
+    // another window doesn't clobber one that hasn't been saved yet; `None`
+    // until loaded
+    let saved = store_value(None::<T>);
+
+    let loaded = create_kv_query(key);
+    create_effect(move |_| {
+        let Some(Ok(value)) = loaded.get() else {
+            return;
+        };
+        let remote = match value.map(serde_json::from_value::<T>) {
+            None => default.clone(),
+            Some(Ok(remote)) => on_load(remote),
+            Some(Err(err)) => {
+                log::warn!("ignoring saved `{key}` that no longer fits: {err}");
+                default.clone()
+            }
+        };
+        let unsaved = saved.with_value(|saved| {
+            saved
+                .as_ref()
+                .is_some_and(|saved| signal.with_untracked(|value| value != saved))
+        });
+        if !unsaved && signal.with_untracked(|value| *value != remote) {
+            signal.set(remote.clone());
+        }
+        saved.set_value(Some(remote));
+    });
+    let settled = create_debounced(signal, SAVE_DELAY);
+    create_effect(move |_| {
+        let value = settled.get();
+        let changed = saved.with_value(|saved| saved.as_ref().is_some_and(|s| *s != value));
+        if !changed || !can_write.get_untracked() {
+            return;
+        }
+        spawn_local(async move {
+            match save(key, &value).await {
+                Ok(()) => saved.set_value(Some(value)),
+                Err(err) => log::warn!("couldn't save `{key}`: {err}"),
+            }
+        });
+    });
+    signal
+}
@@ -1,4 +1,33 @@
 mod app;
+mod auth;
+mod calendar;
+mod cancel;
+mod commands;
+mod debounce;
+mod diff;
+mod formula;
+mod grid;
+mod history;
+mod ipc;
+mod jobs;
+mod kanban;
+mod kv;
+mod menu;
+mod modal;
+mod notes;
+mod optimistic;
+mod outbox;
+mod palette;
+mod policy;
+mod query;
+mod reminders;
+mod search;
+mod settings;
+mod shortcuts;
+mod theme;
+mod toast;
+mod todos;
+mod unsaved;
 
 use app::*;
 use leptos::*;
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{use_can, Capability};
+use crate::commands::use_commands;
+use crate::ipc::{self, NoArgs};
+use crate::shortcuts::{use_shortcuts, Chord};
+
+/// Mirrors the backend's menu items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuAction {
+    AddTodo,
+    OpenViewer,
+    Undo,
+    Redo,
+    TogglePalette,
+    ShowShortcuts,
+    ToggleTheme,
+    About,
+}
+
+impl MenuAction {
+    const ALL: [MenuAction; 8] = [
+        MenuAction::AddTodo,
+        MenuAction::OpenViewer,
+        MenuAction::Undo,
+        MenuAction::Redo,
+        MenuAction::TogglePalette,
+        MenuAction::ShowShortcuts,
+        MenuAction::ToggleTheme,
+        MenuAction::About,
+    ];
+
+    /// The id of the command the item runs.
+    fn command(self) -> &'static str {
+        match self {
+            MenuAction::AddTodo => "todo.add",
+            MenuAction::OpenViewer => "window.open_viewer",
+            MenuAction::Undo => "edit.undo",
+            MenuAction::Redo => "edit.redo",
+            MenuAction::TogglePalette => "palette.toggle",
+            MenuAction::ShowShortcuts => "shortcuts.show",
+            MenuAction::ToggleTheme => "theme.toggle",
+            MenuAction::About => "help.about",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct MenuItemState {
+    action: MenuAction,
+    enabled: bool,
+    checked: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct SetMenuStateArgs<'a> {
+    items: &'a [MenuItemState],
+}
+
+/// Runs the command behind each clicked menu item, and keeps the items'
+/// enabled and checked states in line with their commands. An item whose
+/// command isn't registered is disabled.
+///
+/// The items' accelerators are the commands' shortcuts as they were when the
+/// menu was built, and key presses of them are left to the menu.
+#[component]
+pub fn MenuBridge() -> impl IntoView {
+    let commands = use_commands();
+    ipc::listen("menu://action", move |action: MenuAction| {
+        if !commands.run(action.command()) {
+            log::warn!("menu item {action:?} has no enabled command");
+        }
+    });
+
+    let can_update = use_can(Capability::MenuUpdate);
+    let shortcuts = use_shortcuts();
+    create_effect(move |fetched| {
+        // viewer windows have no menu
+        if fetched == Some(true) || !can_update.get() {
+            return fetched.unwrap_or(false);
+        }
+        spawn_local(async move {
+            match ipc::call::<_, BTreeMap<String, String>>("menu_accelerators", &NoArgs {}).await {
+                Ok(accelerators) => shortcuts.leave_to_menu(
+                    accelerators
+                        .values()
+                        .filter_map(|chord| chord.parse::<Chord>().ok())
+                        .collect(),
+                ),
+                Err(err) => log::warn!("failed to load the menu's accelerators: {err}"),
+            }
+        });
+        true
+    });
+    let list = commands.list();
+    let items = create_memo(move |_| {
+        list.with(|list| {
+            MenuAction::ALL
+                .into_iter()
+                .map(|action| {
+                    let command = list.iter().find(|command| command.id == action.command());
+                    MenuItemState {
+                        action,
+                        enabled: command.is_some_and(|command| command.enabled.get()),
+                        checked: command.and_then(|command| command.checked.map(|c| c.get())),
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+    });
+    create_effect(move |_| {
+        // viewer windows have no menu
+        if !can_update.get() {
+            return;
+        }
+        let items = items.get();
+        spawn_local(async move {
+            let args = SetMenuStateArgs { items: &items };
+            if let Err(err) = ipc::call::<_, ()>("set_menu_state", &args).await {
+                log::warn!("failed to update the menu: {err}");
+            }
+        });
+    });
+}
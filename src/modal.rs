@@ -0,0 +1,241 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use futures::channel::oneshot;
+use leptos::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, KeyboardEvent};
+
+/// Elements that can take focus with Tab inside a modal.
+const FOCUSABLE: &str = "a[href], button:not([disabled]), input:not([disabled]), \
+    select:not([disabled]), textarea:not([disabled]), [tabindex]:not([tabindex='-1'])";
+
+#[derive(Clone)]
+struct Modal {
+    id: u64,
+    content: Rc<dyn Fn() -> View>,
+    on_close: Rc<dyn Fn()>,
+    /// Focused before the modal opened, and focused again once it closes.
+    restore_focus: Option<Element>,
+}
+
+/// Open modals, topmost last.
+///
+/// Only the topmost modal is interactive: it traps focus, and Escape or a
+/// click on its backdrop closes it.
+#[derive(Clone, Copy)]
+pub struct Modals {
+    stack: RwSignal<Vec<Modal>>,
+    next_id: StoredValue<u64>,
+}
+
+/// Closes the modal it was returned for.
+#[derive(Clone, Copy)]
+pub struct ModalHandle {
+    id: u64,
+    modals: Modals,
+}
+
+impl ModalHandle {
+    pub fn close(&self) {
+        self.modals.close(self.id);
+    }
+}
+
+pub fn provide_modals() {
+    provide_context(Modals {
+        stack: create_rw_signal(Vec::new()),
+        next_id: store_value(0),
+    });
+}
+
+pub fn use_modals() -> Modals {
+    expect_context::<Modals>()
+}
+
+impl Modals {
+    /// Opens a modal on top of any others. `on_close` runs however it is
+    /// closed, including by Escape or the backdrop.
+    pub fn open<V: IntoView>(
+        &self,
+        content: impl Fn(ModalHandle) -> V + 'static,
+        on_close: impl Fn() + 'static,
+    ) -> ModalHandle {
+        let id = self.next_id.get_value();
+        self.next_id.set_value(id + 1);
+        let handle = ModalHandle { id, modals: *self };
+        self.stack.update(|stack| {
+            stack.push(Modal {
+                id,
+                content: Rc::new(move || content(handle).into_view()),
+                on_close: Rc::new(on_close),
+                restore_focus: document().active_element(),
+            })
+        });
+        handle
+    }
+
+    pub fn close(&self, id: u64) {
+        let closed = self
+            .stack
+            .try_update(|stack| {
+                let index = stack.iter().position(|modal| modal.id == id)?;
+                Some(stack.remove(index))
+            })
+            .flatten();
+        if let Some(modal) = closed {
+            (modal.on_close)();
+            let restore = modal.restore_focus.and_then(|el| el.dyn_into::<HtmlElement>().ok());
+            if let Some(element) = restore {
+                _ = element.focus();
+            }
+        }
+    }
+
+    fn top(&self) -> Option<u64> {
+        self.stack
+            .with_untracked(|stack| stack.last().map(|modal| modal.id))
+    }
+
+    /// Asks a yes/no question; closing the modal any other way counts as no.
+    ///
+    /// Meant to be awaited from an event handler:
+    /// `spawn_local(async move { if modals.confirm("Remove?").await { .. } })`.
+    pub async fn confirm(&self, message: impl Into<String>) -> bool {
+        let (tx, rx) = oneshot::channel();
+        let tx = Rc::new(Cell::new(Some(tx)));
+        let answer = move |value: bool| {
+            if let Some(tx) = tx.take() {
+                _ = tx.send(value);
+            }
+        };
+        let message = message.into();
+        self.open(
+            {
+                let answer = answer.clone();
+                move |handle: ModalHandle| {
+                    let answer = answer.clone();
+                    let answer_yes = answer.clone();
+                    view! {
+                        <p>{message.clone()}</p>
+                        <div class="modal-buttons">
+                            <button on:click=move |_| {
+                                answer(false);
+                                handle.close();
+                            }>"Cancel"</button>
+                            <button on:click=move |_| {
+                                answer_yes(true);
+                                handle.close();
+                            }>"OK"</button>
+                        </div>
+                    }
+                }
+            },
+            move || answer(false),
+        );
+        // the sender is only dropped without sending if the window is torn down
+        rx.await.unwrap_or(false)
+    }
+}
+
+fn dialog_element(id: u64) -> Option<Element> {
+    document()
+        .query_selector(&format!("[data-modal-id='{id}']"))
+        .ok()
+        .flatten()
+}
+
+fn focusable(dialog: &Element) -> Vec<HtmlElement> {
+    let Ok(nodes) = dialog.query_selector_all(FOCUSABLE) else {
+        return Vec::new();
+    };
+    (0..nodes.length())
+        .filter_map(|i| nodes.item(i))
+        .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+        .collect()
+}
+
+/// Keeps Tab and Shift+Tab cycling within the dialog.
+fn trap_focus(dialog: &Element, ev: &KeyboardEvent) {
+    let elements = focusable(dialog);
+    let (Some(first), Some(last)) = (elements.first(), elements.last()) else {
+        ev.prevent_default();
+        return;
+    };
+    let active = document().active_element();
+    let inside = active
+        .as_ref()
+        .is_some_and(|active| dialog.contains(Some(active.as_ref())));
+    let at = |element: &HtmlElement| active.as_ref() == Some(element.as_ref());
+    if ev.shift_key() && (!inside || at(first)) {
+        ev.prevent_default();
+        _ = last.focus();
+    } else if !ev.shift_key() && (!inside || at(last)) {
+        ev.prevent_default();
+        _ = first.focus();
+    }
+}
+
+/// Renders open modals into a portal at the end of `<body>`. Later modals
+/// stack above earlier ones.
+#[component]
+pub fn ModalHost() -> impl IntoView {
+    let modals = use_modals();
+    let keydown = window_event_listener(ev::keydown, move |ev| {
+        let Some(top) = modals.top() else {
+            return;
+        };
+        match ev.key().as_str() {
+            "Escape" => {
+                ev.prevent_default();
+                modals.close(top);
+            }
+            "Tab" => {
+                if let Some(dialog) = dialog_element(top) {
+                    trap_focus(&dialog, &ev);
+                }
+            }
+            _ => {}
+        }
+    });
+    on_cleanup(move || keydown.remove());
+
+    view! {
+        <Portal>
+            <div class="modal-root">
+                <For
+                    each=move || modals.stack.get()
+                    key=|modal| modal.id
+                    children=move |modal| {
+                        let id = modal.id;
+                        // move focus into the dialog once it is in the document
+                        request_animation_frame(move || {
+                            if let Some(first) = dialog_element(id)
+                                .and_then(|dialog| focusable(&dialog).into_iter().next())
+                            {
+                                _ = first.focus();
+                            }
+                        });
+                        view! {
+                            <div
+                                class="modal-backdrop"
+                                on:click=move |ev| {
+                                    // only clicks on the backdrop itself, not the dialog
+                                    let target = event_target::<HtmlElement>(&ev);
+                                    if target.class_name() == "modal-backdrop" {
+                                        modals.close(id);
+                                    }
+                                }
+                            >
+                                <div class="modal" role="dialog" aria-modal="true" data-modal-id=id>
+                                    {(modal.content)()}
+                                </div>
+                            </div>
+                        }
+                    }
+                />
+
+            </div>
+        </Portal>
+    }
+}
@@ -0,0 +1,434 @@
+use std::time::Duration;
+
+use leptos::ev::MouseEvent;
+use leptos::*;
+use leptos_router::use_query_map;
+use pulldown_cmark::{html, Event, Options, Parser};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use web_sys::Element;
+
+use crate::auth::{use_can, Capability};
+use crate::commands::{register_command, Command};
+use crate::diff::{diff_lines, DiffLine};
+use crate::ipc::{self, NoArgs};
+use crate::modal::{use_modals, ModalHandle};
+use crate::query::create_command_query;
+use crate::shortcuts::{register_shortcut, Scope};
+use crate::toast::{format_time, use_toasts};
+use crate::unsaved::register_unsaved;
+
+/// How long typing has to pause before the note is saved.
+const AUTOSAVE_DELAY: Duration = Duration::from_millis(800);
+
+/// Mirrors the backend's note list entries.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NoteMeta {
+    pub id: Uuid,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Note {
+    #[serde(flatten)]
+    pub meta: NoteMeta,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct NoteArgs {
+    id: Uuid,
+}
+
+#[derive(Serialize)]
+struct CreateNoteArgs<'a> {
+    title: &'a str,
+}
+
+#[derive(Serialize)]
+struct SaveNoteArgs<'a> {
+    id: Uuid,
+    body: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Revision {
+    id: Uuid,
+    saved_at: f64,
+    body: String,
+}
+
+#[derive(Serialize)]
+struct RestoreArgs {
+    id: Uuid,
+    revision: Uuid,
+}
+
+#[derive(Serialize)]
+struct RenameNoteArgs<'a> {
+    id: Uuid,
+    title: &'a str,
+}
+
+/// Renders markdown to HTML.
+///
+/// Raw HTML in the source is shown as text: the preview runs with access to
+/// the backend, so notes mustn't be able to inject scripts.
+fn render_markdown(source: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(source, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events);
+    rendered
+}
+
+fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+/// The notes list next to an editor for the selected note.
+#[component]
+pub fn NotesPage() -> impl IntoView {
+    let toasts = use_toasts();
+    let can_write = use_can(Capability::NoteWrite);
+    // search results link to a note with `?id=`
+    let query = use_query_map();
+    let selected = create_rw_signal(None::<Uuid>);
+    create_effect(move |_| {
+        if let Some(id) = query.with(|query| query.get("id").and_then(|id| id.parse().ok())) {
+            selected.set(Some(id));
+        }
+    });
+    let notes = create_command_query::<_, Vec<NoteMeta>>("list_notes", || NoArgs {});
+    let create = create_action(move |_: &()| async move {
+        let args = CreateNoteArgs { title: "Untitled" };
+        match ipc::call::<_, NoteMeta>("create_note", &args).await {
+            Ok(note) => selected.set(Some(note.id)),
+            Err(err) => toasts.error(format!("Couldn't create a note: {err}")),
+        }
+    });
+    register_command(
+        Command::new("note.create", "New note", move || create.dispatch(()))
+            .enabled(Signal::derive(move || can_write.get() && !create.pending().get())),
+    );
+    register_shortcut("note.create", "Alt+Shift+N", Scope::Route("/notes"));
+
+    view! {
+        <section class="notes">
+            <aside class="note-list">
+                <button
+                    on:click=move |_| create.dispatch(())
+                    disabled=move || !can_write.get() || create.pending().get()
+                >
+                    "New note"
+                </button>
+                <ul>
+                    <For
+                        each=move || notes.get().and_then(Result::ok).unwrap_or_default()
+                        // renaming re-renders the entry
+                        key=|note| (note.id, note.title.clone())
+                        children=move |note| {
+                            let id = note.id;
+                            view! {
+                                <li class:selected=move || selected.get() == Some(id)>
+                                    <button on:click=move |_| selected.set(Some(id))>
+                                        {note.title}
+                                    </button>
+                                </li>
+                            }
+                        }
+                    />
+
+                </ul>
+            </aside>
+            // a fresh editor per note, so switching notes saves the last one
+            {move || {
+                selected
+                    .get()
+                    .map(|id| view! { <NoteEditor id on_deleted=move |_| selected.set(None)/> })
+            }}
+
+        </section>
+    }
+}
+
+/// Edits a note's markdown next to a live preview, saving shortly after
+/// typing stops and again when closed.
+#[component]
+fn NoteEditor(id: Uuid, #[prop(into)] on_deleted: Callback<()>) -> impl IntoView {
+    let toasts = use_toasts();
+    let modals = use_modals();
+    let can_write = use_can(Capability::NoteWrite);
+    let can_delete = use_can(Capability::NoteDelete);
+    let note = create_command_query::<_, Note>("read_note", move || NoteArgs { id });
+
+    let title = create_rw_signal(String::new());
+    let draft = create_rw_signal(String::new());
+    // the body as last saved, `None` until the note has loaded
+    let saved = create_rw_signal(None::<String>);
+    let saving = create_rw_signal(false);
+    let dirty = Signal::derive(move || {
+        saved.with(|saved| saved.as_ref().is_some_and(|saved| draft.with(|d| d != saved)))
+    });
+    register_unsaved("Note", dirty);
+
+    create_effect(move |_| match note.get() {
+        Some(Ok(note)) => {
+            title.set(note.meta.title);
+            // keep what's being typed over changes saved elsewhere
+            if !dirty.get_untracked() {
+                draft.set(note.body.clone());
+            }
+            saved.set(Some(note.body));
+        }
+        Some(Err(err)) => toasts.error(format!("Couldn't open the note: {err}")),
+        None => {}
+    });
+
+    // Reads the draft right away, since this also runs on cleanup, and saves
+    // it once awaited.
+    let save = move || {
+        let body = match (draft.try_get_untracked(), saved.try_get_untracked()) {
+            (Some(body), Some(Some(last)))
+                if body != last && can_write.try_get_untracked().unwrap_or(false) =>
+            {
+                Some(body)
+            }
+            _ => None,
+        };
+        async move {
+            let Some(body) = body else {
+                return;
+            };
+            saving.try_set(true);
+            let args = SaveNoteArgs { id, body: &body };
+            match ipc::call::<_, NoteMeta>("save_note", &args).await {
+                Ok(_) => {
+                    saved.try_set(Some(body));
+                }
+                Err(err) => toasts.error(format!("Couldn't save the note: {err}")),
+            }
+            saving.try_set(false);
+        }
+    };
+    let pending = store_value(None::<TimeoutHandle>);
+    let cancel_autosave = move || {
+        if let Some(handle) = pending.try_update_value(Option::take).flatten() {
+            handle.clear();
+        }
+    };
+    let schedule_autosave = move || {
+        cancel_autosave();
+        let handle = set_timeout_with_handle(move || spawn_local(save()), AUTOSAVE_DELAY);
+        pending.set_value(handle.ok());
+    };
+    on_cleanup(move || {
+        cancel_autosave();
+        spawn_local(save());
+    });
+
+    let rename = move |new_title: String| {
+        title.set(new_title.clone());
+        spawn_local(async move {
+            let args = RenameNoteArgs {
+                id,
+                title: &new_title,
+            };
+            if let Err(err) = ipc::call::<_, NoteMeta>("rename_note", &args).await {
+                toasts.error(format!("Couldn't rename the note: {err}"));
+            }
+        });
+    };
+    let delete = move |_| {
+        spawn_local(async move {
+            let title = title.get_untracked();
+            if !modals.confirm(format!("Delete \"{title}\"?")).await {
+                return;
+            }
+            // nothing left to save once it's gone
+            cancel_autosave();
+            saved.set(Some(draft.get_untracked()));
+            match ipc::call::<_, ()>("delete_note", &NoteArgs { id }).await {
+                Ok(()) => on_deleted.call(()),
+                Err(err) => toasts.error(format!("Couldn't delete the note: {err}")),
+            }
+        });
+    };
+    let restore = Callback::new(move |revision: Uuid| {
+        cancel_autosave();
+        spawn_local(async move {
+            // so what hasn't been saved yet is in the history too
+            save().await;
+            let args = RestoreArgs { id, revision };
+            if let Err(err) = ipc::call::<_, NoteMeta>("restore_note_revision", &args).await {
+                toasts.error(format!("Couldn't restore the note: {err}"));
+            }
+        });
+    });
+    let show_history = move |_| {
+        modals.open(move |handle| view! { <NoteHistory id handle on_restore=restore/> }, || {});
+    };
+    // links would navigate the whole window away from the app
+    let block_links = |ev: MouseEvent| {
+        let target = event_target::<Element>(&ev);
+        if target.closest("a").ok().flatten().is_some() {
+            ev.prevent_default();
+        }
+    };
+
+    view! {
+        <div class="note-editor">
+            <header>
+                <input
+                    type="text"
+                    aria-label="Title"
+                    prop:value=title
+                    readonly=move || !can_write.get()
+                    on:change=move |ev| rename(event_target_value(&ev))
+                />
+                <button on:click=show_history>"History"</button>
+                <button on:click=delete disabled=move || !can_delete.get()>
+                    "Delete"
+                </button>
+            </header>
+            <div class="note-panes">
+                <textarea
+                    aria-label="Markdown"
+                    prop:value=draft
+                    readonly=move || !can_write.get()
+                    on:input=move |ev| {
+                        draft.set(event_target_value(&ev));
+                        schedule_autosave();
+                    }
+                ></textarea>
+                <div
+                    class="note-preview"
+                    on:click=block_links
+                    inner_html=move || draft.with(|draft| render_markdown(draft))
+                ></div>
+            </div>
+            <footer>
+                {move || draft.with(|draft| word_count(draft))}
+                " words · "
+                {move || {
+                    if saving.get() {
+                        "Saving..."
+                    } else if dirty.get() {
+                        "Unsaved"
+                    } else {
+                        "Saved"
+                    }
+                }}
+
+            </footer>
+        </div>
+    }
+}
+
+/// Compares two saved versions of a note, and restores either one.
+#[component]
+fn NoteHistory(id: Uuid, handle: ModalHandle, on_restore: Callback<Uuid>) -> impl IntoView {
+    let can_write = use_can(Capability::NoteWrite);
+    let revisions = create_command_query::<_, Vec<Revision>>("list_note_revisions", move || {
+        NoteArgs { id }
+    });
+    let list = move || revisions.get().and_then(Result::ok).unwrap_or_default();
+    // picked by the user, or else the latest two revisions
+    let from = create_rw_signal(None::<Uuid>);
+    let to = create_rw_signal(None::<Uuid>);
+    let from_id = Signal::derive(move || {
+        from.get().or_else(|| list().iter().take(2).last().map(|r| r.id))
+    });
+    let to_id = Signal::derive(move || to.get().or_else(|| list().first().map(|r| r.id)));
+
+    let picker = move |label: &'static str,
+                       picked: Signal<Option<Uuid>>,
+                       pick: RwSignal<Option<Uuid>>| {
+        view! {
+            <label>
+                {label}
+                " "
+                <select on:change=move |ev| pick.set(event_target_value(&ev).parse().ok())>
+                    {move || {
+                        list()
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, revision)| {
+                                let latest = if index == 0 { " (current)" } else { "" };
+                                view! {
+                                    <option
+                                        value=revision.id.to_string()
+                                        selected=move || picked.get() == Some(revision.id)
+                                    >
+                                        {format_time(revision.saved_at)}
+                                        {latest}
+                                    </option>
+                                }
+                            })
+                            .collect_view()
+                    }}
+
+                </select>
+            </label>
+            <button
+                disabled=move || !can_write.get() || picked.get().is_none()
+                on:click=move |_| {
+                    if let Some(revision) = picked.get_untracked() {
+                        handle.close();
+                        on_restore.call(revision);
+                    }
+                }
+            >
+                "Restore"
+            </button>
+        }
+    };
+
+    let diff = move || {
+        let revisions = list();
+        let body = |picked: Option<Uuid>| {
+            revisions
+                .iter()
+                .find(|r| Some(r.id) == picked)
+                .map_or("", |r| r.body.as_str())
+        };
+        let lines = diff_lines(body(from_id.get()), body(to_id.get()));
+        if lines.iter().all(|line| matches!(line, DiffLine::Same(_))) {
+            return view! { <p>"No differences."</p> }.into_view();
+        }
+        let lines = lines
+            .into_iter()
+            .map(|line| {
+                let (class, text) = match line {
+                    DiffLine::Same(text) => ("same", format!("  {text}")),
+                    DiffLine::Added(text) => ("added", format!("+ {text}")),
+                    DiffLine::Removed(text) => ("removed", format!("- {text}")),
+                };
+                view! { <div class=class>{text}</div> }
+            })
+            .collect_view();
+        view! { <pre class="diff">{lines}</pre> }.into_view()
+    };
+
+    view! {
+        <div class="note-history">
+            <h2>"History"</h2>
+            <Show
+                when=move || !list().is_empty()
+                fallback=|| view! { <p>"Nothing saved yet."</p> }
+            >
+                <div class="revision-pickers">
+                    {picker("From", from_id, from)} {picker("To", to_id, to)}
+                </div>
+                {diff}
+            </Show>
+            <div class="modal-buttons">
+                <button on:click=move |_| handle.close()>"Close"</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,67 @@
+use std::future::Future;
+use std::rc::Rc;
+
+use leptos::*;
+
+use crate::cancel::CancelToken;
+use crate::ipc::IpcError;
+use crate::policy::{retry, CallPolicy};
+use crate::toast::{use_toasts, ToastAction, ToastLevel};
+
+/// Like `create_action`, but the change shows up before the backend answers.
+///
+/// On dispatch, `apply` updates local signals with the predicted result and
+/// returns whatever is needed to find that change again (e.g. a temporary
+/// id). `mutate` then calls the backend, retried according to `policy`. On
+/// success `reconcile` swaps the prediction for the real response; on failure
+/// `rollback` undoes it and an error toast offers to dispatch the input again.
+pub fn create_optimistic_action<I, O, P, Fu>(
+    policy: CallPolicy,
+    apply: impl Fn(&I) -> P + 'static,
+    mutate: impl Fn(&I, CancelToken) -> Fu + 'static,
+    reconcile: impl Fn(P, &O) + 'static,
+    rollback: impl Fn(P) + 'static,
+) -> Action<I, Result<O, IpcError>>
+where
+    I: Clone + 'static,
+    O: 'static,
+    P: 'static,
+    Fu: Future<Output = Result<O, IpcError>> + 'static,
+{
+    let toasts = use_toasts();
+    let attempts = create_rw_signal(0);
+    let mutate = Rc::new(mutate);
+    let reconcile = Rc::new(reconcile);
+    let rollback = Rc::new(rollback);
+    // lets the Retry button dispatch to the action being created
+    let this = store_value(None::<Action<I, Result<O, IpcError>>>);
+    let action = create_action(move |input: &I| {
+        let patch = apply(input);
+        let input = input.clone();
+        let mutate = Rc::clone(&mutate);
+        let reconcile = Rc::clone(&reconcile);
+        let rollback = Rc::clone(&rollback);
+        async move {
+            let token = CancelToken::new();
+            let result = retry(policy, attempts, &token, |child| mutate(&input, child)).await;
+            match &result {
+                Ok(output) => reconcile(patch, output),
+                Err(err) => {
+                    rollback(patch);
+                    let retry = (!err.is_forbidden() && !err.is_cancelled()).then(|| {
+                        let input = input.clone();
+                        ToastAction::retry(move || {
+                            if let Some(action) = this.get_value() {
+                                action.dispatch(input.clone());
+                            }
+                        })
+                    });
+                    toasts.notify(ToastLevel::Error, format!("Change was undone. {err}"), retry);
+                }
+            }
+            result
+        }
+    });
+    this.set_value(Some(action));
+    action
+}
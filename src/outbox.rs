@@ -0,0 +1,188 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ipc::{self, IpcError, NoArgs};
+use crate::optimistic::create_optimistic_action;
+use crate::policy::CallPolicy;
+use crate::toast::use_toasts;
+use crate::todos::{Status, TodoDetails};
+
+/// Mirrors the backend's mutations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Mutation {
+    AddTodo {
+        id: Uuid,
+        text: String,
+        #[serde(default)]
+        details: TodoDetails,
+    },
+    UpdateTodo {
+        id: Uuid,
+        text: String,
+        details: TodoDetails,
+    },
+    /// `next_id` is the id of the next occurrence, if the todo recurs.
+    CompleteTodo { id: Uuid, next_id: Uuid },
+    ReopenTodo { id: Uuid, next_id: Uuid },
+    SetTodoStatus {
+        id: Uuid,
+        status: Status,
+        next_id: Uuid,
+    },
+    DeleteTodo { id: Uuid },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub idempotency_key: Uuid,
+    pub mutation: Mutation,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl OutboxEntry {
+    pub fn new(mutation: Mutation) -> Self {
+        Self {
+            idempotency_key: Uuid::new_v4(),
+            mutation,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EnqueueArgs<'a> {
+    entry: &'a OutboxEntry,
+}
+
+#[derive(Deserialize)]
+struct Failed {
+    entry: OutboxEntry,
+    error: String,
+}
+
+type OutboxAction = Action<OutboxEntry, Result<Vec<OutboxEntry>, IpcError>>;
+
+/// The mutations not applied yet: those the backend has queued, plus those
+/// still on their way to it.
+#[derive(Clone, Copy)]
+pub struct Outbox {
+    queued: RwSignal<Vec<OutboxEntry>>,
+    sending: RwSignal<Vec<OutboxEntry>>,
+    /// Owned by the app rather than a component, as undo and redo in the
+    /// history and toasts can dispatch it long after the component that
+    /// queued the change is gone.
+    action: OutboxAction,
+}
+
+pub fn provide_outbox() {
+    let queued = create_rw_signal(Vec::new());
+    let sending = create_rw_signal(Vec::new());
+    let outbox = Outbox {
+        queued,
+        sending,
+        action: create_outbox_action(queued, sending),
+    };
+    let toasts = use_toasts();
+    ipc::listen("outbox://changed", move |queued: Vec<OutboxEntry>| {
+        outbox.queued.set(queued)
+    });
+    ipc::listen("outbox://failed", move |failed: Failed| {
+        log::warn!("dropped {:?}: {}", failed.entry.mutation, failed.error);
+        toasts.error(format!("A change couldn't be saved: {}", failed.error));
+    });
+    spawn_local(async move {
+        match ipc::call::<_, Vec<OutboxEntry>>("list_outbox", &NoArgs {}).await {
+            Ok(queued) => outbox.queued.set(queued),
+            Err(err) => log::warn!("failed to load the outbox: {err}"),
+        }
+    });
+    provide_context(outbox);
+}
+
+pub fn use_outbox() -> Outbox {
+    expect_context::<Outbox>()
+}
+
+impl Outbox {
+    /// Hands `mutation` to the backend's outbox.
+    pub fn queue(&self, mutation: Mutation) {
+        self.action.dispatch(OutboxEntry::new(mutation));
+    }
+
+    /// The action [`Outbox::queue`] dispatches, for its pending state.
+    pub fn action(&self) -> OutboxAction {
+        self.action
+    }
+
+    /// Everything not applied yet, oldest first.
+    pub fn pending(&self) -> Signal<Vec<OutboxEntry>> {
+        let Outbox { queued, sending, .. } = *self;
+        Signal::derive(move || {
+            let mut pending = queued.get();
+            let unsent = sending.with(|sending| {
+                sending
+                    .iter()
+                    .filter(|entry| {
+                        pending
+                            .iter()
+                            .all(|e| e.idempotency_key != entry.idempotency_key)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>()
+            });
+            pending.extend(unsent);
+            pending
+        })
+    }
+}
+
+/// An action that hands mutations to the backend's outbox.
+///
+/// The entry counts as pending from the moment it is dispatched. Once the
+/// backend has saved it, it is replayed in order until it applies, even
+/// across restarts. Enqueueing is retried and deduplicated by the entry's
+/// idempotency key; only if the backend can't be reached at all is the entry
+/// dropped again, with an error toast.
+fn create_outbox_action(
+    queued: RwSignal<Vec<OutboxEntry>>,
+    sending: RwSignal<Vec<OutboxEntry>>,
+) -> OutboxAction {
+    create_optimistic_action(
+        CallPolicy::for_command("enqueue_mutation"),
+        move |entry: &OutboxEntry| {
+            sending.update(|sending| sending.push(entry.clone()));
+            entry.idempotency_key
+        },
+        |entry: &OutboxEntry, _| {
+            let entry = entry.clone();
+            async move { ipc::call("enqueue_mutation", &EnqueueArgs { entry: &entry }).await }
+        },
+        move |key, saved: &Vec<OutboxEntry>| {
+            queued.set(saved.clone());
+            sending.update(|sending| sending.retain(|e| e.idempotency_key != key));
+        },
+        move |key| sending.update(|sending| sending.retain(|e| e.idempotency_key != key)),
+    )
+}
+
+#[component]
+pub fn PendingChanges() -> impl IntoView {
+    let pending = use_outbox().pending();
+    let count = move || pending.with(Vec::len);
+    let problem = move || pending.with(|p| p.first().and_then(|e| e.last_error.clone()));
+    view! {
+        <Show when=move || { count() > 0 }>
+            <p class="pending-changes">
+                {count}
+                {move || if count() == 1 { " change pending" } else { " changes pending" }}
+                {move || problem().map(|err| format!(" (retrying: {err})"))}
+            </p>
+        </Show>
+    }
+}
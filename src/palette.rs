@@ -0,0 +1,168 @@
+use leptos::ev::KeyboardEvent;
+use leptos::*;
+
+use crate::commands::{register_command, use_commands, Command};
+use crate::modal::{use_modals, ModalHandle};
+use crate::shortcuts::{register_shortcut, Scope};
+
+/// Scores how well `query` matches `text` as a subsequence, ignoring case
+/// and spaces in the query, or `None` if it doesn't match. Consecutive
+/// matches and matches at the start of a word score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut from = 0;
+    let mut previous = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = from + text[from..].iter().position(|&t| t == c)?;
+        score += 1;
+        if previous.is_some_and(|previous| found == previous + 1) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // skipping ahead a little is fine, a lot is a worse match
+        score -= (found - from).min(3) as i32;
+        previous = Some(found);
+        from = found + 1;
+    }
+    Some(score)
+}
+
+/// What the palette lists for `query`: with no query, recent commands first
+/// and then the rest; otherwise the fuzzy matches, best first.
+fn matches(query: &str, commands: Vec<Command>, recent: &[String]) -> Vec<(Command, bool)> {
+    let is_recent = |command: &Command| recent.contains(&command.id);
+    if query.trim().is_empty() {
+        let mut listed = commands
+            .into_iter()
+            .map(|command| {
+                let rank = recent.iter().position(|id| *id == command.id);
+                (command, rank)
+            })
+            .collect::<Vec<_>>();
+        // stable, so everything not recent keeps its registration order
+        listed.sort_by_key(|(_, rank)| rank.unwrap_or(usize::MAX));
+        return listed
+            .into_iter()
+            .map(|(command, rank)| (command, rank.is_some()))
+            .collect();
+    }
+    let mut scored = commands
+        .into_iter()
+        .filter_map(|command| Some((fuzzy_score(query, &command.title)?, command)))
+        .collect::<Vec<_>>();
+    scored.sort_by_key(|(score, command)| (-score, !is_recent(command)));
+    scored
+        .into_iter()
+        .map(|(_, command)| {
+            let recent = is_recent(&command);
+            (command, recent)
+        })
+        .collect()
+}
+
+#[component]
+fn Palette(handle: ModalHandle) -> impl IntoView {
+    let commands = use_commands();
+    let query = create_rw_signal(String::new());
+    let selected = create_rw_signal(0usize);
+    let list = commands.list();
+    let recent = commands.recent();
+    let results = Signal::derive(move || {
+        recent.with(|recent| matches(&query.get(), list.get(), recent))
+    });
+    let run = move |id: String| {
+        // close first, so the command can move focus where it wants
+        handle.close();
+        commands.run(&id);
+    };
+    let on_keydown = move |ev: KeyboardEvent| {
+        let count = results.with(Vec::len);
+        match ev.key().as_str() {
+            "ArrowDown" => {
+                ev.prevent_default();
+                selected.update(|i| *i = (*i + 1).min(count.saturating_sub(1)));
+            }
+            "ArrowUp" => {
+                ev.prevent_default();
+                selected.update(|i| *i = i.saturating_sub(1));
+            }
+            "Enter" => {
+                ev.prevent_default();
+                let id = results.with(|results| {
+                    results
+                        .get(selected.get_untracked())
+                        .map(|(command, _)| command.id.clone())
+                });
+                if let Some(id) = id {
+                    run(id);
+                }
+            }
+            _ => {}
+        }
+    };
+
+    view! {
+        <div class="palette">
+            <input
+                type="text"
+                placeholder="Type a command..."
+                aria-label="Command"
+                prop:value=query
+                on:input=move |ev| {
+                    query.set(event_target_value(&ev));
+                    selected.set(0);
+                }
+                on:keydown=on_keydown
+            />
+            <ul role="listbox">
+                {move || {
+                    results
+                        .get()
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, (command, recent))| {
+                            let Command { id, title, enabled, .. } = command;
+                            view! {
+                                <li
+                                    role="option"
+                                    class:selected=move || selected.get() == index
+                                    class:disabled=move || !enabled.get()
+                                    aria-selected=move || (selected.get() == index).to_string()
+                                    on:mouseenter=move |_| selected.set(index)
+                                    on:click=move |_| run(id.clone())
+                                >
+                                    {title}
+                                    {recent.then(|| view! { <span class="recent">"recent"</span> })}
+                                </li>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </ul>
+        </div>
+    }
+}
+
+/// Opens the command palette on Ctrl+K (Cmd+K on macOS), and closes it on a
+/// second press.
+#[component]
+pub fn CommandPalette() -> impl IntoView {
+    let modals = use_modals();
+    let open = store_value(None::<ModalHandle>);
+    register_command(Command::new("palette.toggle", "Show command palette", move || {
+        match open.get_value() {
+            Some(handle) => handle.close(),
+            None => {
+                let handle = modals.open(
+                    |handle| view! { <Palette handle/> },
+                    move || open.set_value(None),
+                );
+                open.set_value(Some(handle));
+            }
+        }
+    }));
+    register_shortcut("palette.toggle", "Ctrl+K", Scope::Global);
+}
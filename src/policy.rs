@@ -0,0 +1,153 @@
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+
+use futures::future::{select, Either};
+use gloo_timers::future::TimeoutFuture;
+use leptos::*;
+
+use crate::cancel::CancelToken;
+use crate::ipc::{ErrorKind, IpcError};
+
+/// How a backend call is timed out and retried.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CallPolicy {
+    /// Per attempt; `None` waits forever.
+    pub timeout: Option<Duration>,
+    /// Including the first attempt, so `1` means no retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every retry after that.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of each delay, between 0 and 1, that is randomized so
+    /// windows retrying together don't stay in lockstep.
+    pub jitter: f64,
+    /// Whether running the command twice has the same effect as running it
+    /// once. Only idempotent commands are retried after a timeout, since the
+    /// first attempt may still have gone through.
+    pub idempotent: bool,
+}
+
+impl Default for CallPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(10)),
+            max_attempts: 1,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: 0.5,
+            idempotent: false,
+        }
+    }
+}
+
+impl CallPolicy {
+    /// The policy for a backend command.
+    pub fn for_command(cmd: &str) -> Self {
+        match cmd {
+            "load_data" => Self::default()
+                .timeout(Duration::from_secs(3))
+                .max_attempts(3)
+                .idempotent(true),
+            "list_todos" | "list_jobs" | "list_notifications" | "get_settings"
+            | "my_capabilities" | "list_notes" | "read_note" | "list_reminders" | "kv_get" => {
+                Self::default().max_attempts(3).idempotent(true)
+            }
+            // the backend drops duplicate idempotency keys
+            "enqueue_mutation" => Self::default()
+                .timeout(Duration::from_secs(5))
+                .max_attempts(5)
+                .idempotent(true),
+            "start_job" => Self::default()
+                .timeout(Duration::from_secs(5))
+                .max_attempts(3),
+            _ => Self::default(),
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = idempotent;
+        self
+    }
+
+    /// Whether an attempt that failed with `err` may be tried again.
+    pub fn should_retry(&self, err: &IpcError) -> bool {
+        match err.kind {
+            ErrorKind::Busy => true,
+            ErrorKind::Timeout => self.idempotent,
+            _ => false,
+        }
+    }
+
+    /// How long to wait after failed attempt number `attempt` (1-based).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0) * js_sys::Math::random();
+        exp.mul_f64(1.0 - jitter)
+    }
+}
+
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    fut: impl Future<Output = Result<T, IpcError>>,
+) -> Result<T, IpcError> {
+    let Some(timeout) = timeout else {
+        return fut.await;
+    };
+    let timer = TimeoutFuture::new(timeout.as_millis().try_into().unwrap_or(u32::MAX));
+    match select(pin!(fut), timer).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(IpcError::timeout(format!("no response after {timeout:?}"))),
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails for good, or `policy` runs out of
+/// attempts.
+///
+/// Every attempt gets its own child of `token`, which is cancelled when that
+/// attempt times out. `attempts` is set to the number of the running attempt.
+pub async fn retry<T, Fu>(
+    policy: CallPolicy,
+    attempts: RwSignal<u32>,
+    token: &CancelToken,
+    attempt: impl Fn(CancelToken) -> Fu,
+) -> Result<T, IpcError>
+where
+    Fu: Future<Output = Result<T, IpcError>>,
+{
+    let mut n = 1;
+    loop {
+        if token.is_cancelled() || attempts.try_set(n).is_some() {
+            // cancelled, or the owner of `attempts` is gone
+            return Err(IpcError::cancelled());
+        }
+        let child = token.child();
+        let result = with_timeout(policy.timeout, attempt(child.clone())).await;
+        match result {
+            Err(err) if err.kind == ErrorKind::Timeout => {
+                child.cancel();
+                if n >= policy.max_attempts || !policy.should_retry(&err) {
+                    return Err(err);
+                }
+            }
+            Err(err) if n < policy.max_attempts && policy.should_retry(&err) => {}
+            result => return result,
+        }
+        let delay = policy.delay(n);
+        TimeoutFuture::new(delay.as_millis().try_into().unwrap_or(u32::MAX)).await;
+        n += 1;
+    }
+}
@@ -0,0 +1,493 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+use futures::future::{FutureExt, LocalBoxFuture, Shared};
+use leptos::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::CancelToken;
+use crate::ipc::{self, IpcError};
+use crate::policy::{retry, CallPolicy};
+
+/// Cached values older than this are served, then refetched in the background.
+const STALE_AFTER_MS: f64 = 5_000.0;
+/// Cached values no query has read for this long are dropped.
+const EVICT_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// Identifies a cached query: its name followed by its arguments as JSON,
+/// e.g. `load_data:{"value":3}`.
+///
+/// Invalidating a name such as `load_data` hits every argument combination;
+/// invalidating a whole key hits just that one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryKey(String);
+
+impl QueryKey {
+    pub fn new(name: &str, args: &impl Serialize) -> Self {
+        let args = serde_json::to_string(args).expect("query arguments to serialize");
+        Self(format!("{name}:{args}"))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A query result, type-erased so one cache can hold every query.
+type Erased = Rc<dyn Any>;
+type Loader = Rc<dyn Fn(CancelToken) -> LocalBoxFuture<'static, Erased>>;
+
+struct Entry {
+    value: Option<Erased>,
+    fetched_at: f64,
+    stale: bool,
+    loader: Option<Loader>,
+    in_flight: Option<Shared<LocalBoxFuture<'static, Erased>>>,
+    /// The token of the load in flight.
+    token: Option<CancelToken>,
+    /// Whether the load in flight was started for resources waiting on it,
+    /// rather than to refresh a value in the background.
+    foreground: bool,
+    /// How many resources are waiting on the load in flight. A foreground
+    /// load is cancelled once none are.
+    waiting: usize,
+    /// Bumped when a background refetch changes `value`.
+    version: RwSignal<u64>,
+    /// The attempt number of the latest command fetch, see
+    /// [`create_command_query_with_attempts`].
+    attempts: RwSignal<u32>,
+    /// How many queries read this entry; it's evicted once none have for
+    /// [`EVICT_AFTER`].
+    subscribers: usize,
+    /// Bumped whenever `subscribers` drops to zero, so only the latest
+    /// eviction goes ahead.
+    unsubscribed: u64,
+}
+
+impl Entry {
+    fn is_stale(&self) -> bool {
+        self.stale || js_sys::Date::now() - self.fetched_at > STALE_AFTER_MS
+    }
+}
+
+/// Payload of the backend's `data://changed` event.
+#[derive(Deserialize)]
+struct Changed {
+    keys: Vec<String>,
+}
+
+/// Caches query results by [`QueryKey`] and keeps them fresh. Results no
+/// query reads any more are dropped after [`EVICT_AFTER`].
+#[derive(Clone, Copy)]
+pub struct QueryClient {
+    owner: Owner,
+    entries: StoredValue<HashMap<QueryKey, Entry>>,
+}
+
+pub fn provide_query_client() {
+    let client = QueryClient {
+        owner: Owner::current().expect("to be called inside a component"),
+        entries: store_value(HashMap::new()),
+    };
+    // the backend names the queries a mutation affected
+    ipc::listen("data://changed", move |changed: Changed| {
+        for prefix in changed.keys {
+            client.invalidate(&prefix);
+        }
+    });
+    provide_context(client);
+}
+
+pub fn use_query_client() -> QueryClient {
+    expect_context::<QueryClient>()
+}
+
+impl QueryClient {
+    /// The version signal of `key`, creating an empty entry if needed.
+    fn version(&self, key: &QueryKey) -> RwSignal<u64> {
+        let existing = self
+            .entries
+            .with_value(|entries| entries.get(key).map(|entry| entry.version));
+        existing.unwrap_or_else(|| {
+            // owned by the client, so it outlives the component that asked
+            let (version, attempts) =
+                with_owner(self.owner, || (create_rw_signal(0), create_rw_signal(0)));
+            self.entries.update_value(|entries| {
+                entries.insert(
+                    key.clone(),
+                    Entry {
+                        value: None,
+                        fetched_at: 0.0,
+                        stale: true,
+                        loader: None,
+                        in_flight: None,
+                        token: None,
+                        foreground: false,
+                        waiting: 0,
+                        version,
+                        attempts,
+                        subscribers: 0,
+                        unsubscribed: 0,
+                    },
+                );
+            });
+            version
+        })
+    }
+
+    /// The attempts signal of `key`, creating an empty entry if needed.
+    fn attempts(&self, key: &QueryKey) -> RwSignal<u32> {
+        self.version(key);
+        self.entries.with_value(|entries| entries[key].attempts)
+    }
+
+    /// Notes that a query reads `key`, so its entry is kept.
+    fn subscribe(&self, key: &QueryKey) {
+        self.version(key);
+        self.entries.update_value(|entries| {
+            if let Some(entry) = entries.get_mut(key) {
+                entry.subscribers += 1;
+            }
+        });
+    }
+
+    /// Notes that a query stopped reading `key`, evicting its entry after
+    /// [`EVICT_AFTER`] unless another query reads it by then.
+    fn unsubscribe(&self, key: &QueryKey) {
+        let unsubscribed = self
+            .entries
+            .try_update_value(|entries| {
+                let entry = entries.get_mut(key)?;
+                entry.subscribers = entry.subscribers.saturating_sub(1);
+                (entry.subscribers == 0).then(|| {
+                    entry.unsubscribed += 1;
+                    entry.unsubscribed
+                })
+            })
+            .flatten();
+        if let Some(unsubscribed) = unsubscribed {
+            let this = *self;
+            let key = key.clone();
+            set_timeout(move || this.evict(&key, unsubscribed), EVICT_AFTER);
+        }
+    }
+
+    fn evict(&self, key: &QueryKey, unsubscribed: u64) {
+        let evicted = self
+            .entries
+            .try_update_value(|entries| {
+                let entry = entries.get(key)?;
+                if entry.subscribers > 0 || entry.unsubscribed != unsubscribed {
+                    return None;
+                }
+                entries.remove(key)
+            })
+            .flatten();
+        if let Some(entry) = evicted {
+            if let Some(token) = entry.token {
+                token.cancel();
+            }
+            entry.version.dispose();
+            entry.attempts.dispose();
+        }
+    }
+
+    /// Starts `loader` for `key` unless a load is already running, in which
+    /// case that one is shared. A cancelled load isn't shared, and its result
+    /// isn't cached.
+    fn load(
+        &self,
+        key: &QueryKey,
+        loader: Loader,
+        background: bool,
+    ) -> Shared<LocalBoxFuture<'static, Erased>> {
+        self.version(key);
+        let running = self.entries.with_value(|entries| {
+            let entry = entries.get(key)?;
+            let cancelled = entry.token.as_ref().is_some_and(CancelToken::is_cancelled);
+            entry.in_flight.clone().filter(|_| !cancelled)
+        });
+        if let Some(running) = running {
+            return running;
+        }
+        let this = *self;
+        let settle_key = key.clone();
+        let token = CancelToken::new();
+        let settle_token = token.clone();
+        let fut = loader(token.clone());
+        let fut = async move {
+            let value = fut.await;
+            settle_token.finish();
+            let version = this
+                .entries
+                .try_update_value(|entries| {
+                    let entry = entries.get_mut(&settle_key)?;
+                    // a later load may have replaced this cancelled one
+                    if entry.token.as_ref().is_some_and(|t| t.same(&settle_token)) {
+                        entry.in_flight = None;
+                        entry.token = None;
+                    }
+                    if settle_token.is_cancelled() {
+                        return None;
+                    }
+                    entry.value = Some(Rc::clone(&value));
+                    entry.fetched_at = js_sys::Date::now();
+                    entry.stale = false;
+                    Some(entry.version)
+                })
+                .flatten();
+            // outside the update above: subscribers read the entries again
+            if let Some(version) = version.filter(|_| background) {
+                version.update(|version| *version += 1);
+            }
+            value
+        }
+        .boxed_local()
+        .shared();
+        self.entries.update_value(|entries| {
+            if let Some(entry) = entries.get_mut(key) {
+                entry.loader = Some(loader);
+                entry.in_flight = Some(fut.clone());
+                entry.token = Some(token);
+                entry.foreground = !background;
+            }
+        });
+        fut
+    }
+
+    /// Notes that a resource is waiting on `key`.
+    fn wait(&self, key: &QueryKey) {
+        self.entries.update_value(|entries| {
+            if let Some(entry) = entries.get_mut(key) {
+                entry.waiting += 1;
+            }
+        });
+    }
+
+    /// Notes that a resource stopped waiting on `key`, cancelling its load if
+    /// that was started for the resources waiting and none are left.
+    fn release(&self, key: &QueryKey) {
+        let abandoned = self
+            .entries
+            .try_update_value(|entries| {
+                let entry = entries.get_mut(key)?;
+                entry.waiting = entry.waiting.saturating_sub(1);
+                (entry.waiting == 0 && entry.foreground)
+                    .then(|| entry.token.clone())
+                    .flatten()
+            })
+            .flatten();
+        if let Some(token) = abandoned {
+            token.cancel();
+        }
+    }
+
+    fn revalidate(&self, key: &QueryKey, loader: Loader) {
+        let fut = self.load(key, loader, true);
+        spawn_local(async move {
+            fut.await;
+        });
+    }
+
+    /// Serves the cached value for `key` if there is one, refetching it in the
+    /// background when stale. Otherwise waits for `loader`.
+    async fn fetch<T: Clone + 'static>(&self, key: QueryKey, loader: Loader) -> T {
+        let cached = self.entries.with_value(|entries| {
+            entries
+                .get(&key)
+                .and_then(|entry| Some((entry.value.clone()?, entry.is_stale())))
+        });
+        let value = match cached {
+            Some((value, stale)) => {
+                if stale {
+                    self.revalidate(&key, loader);
+                }
+                value
+            }
+            None => self.load(&key, loader, false).await,
+        };
+        value
+            .downcast_ref::<T>()
+            .cloned()
+            .unwrap_or_else(|| panic!("query `{}` used with two result types", key.as_str()))
+    }
+
+    /// Marks the queries named `prefix`, or the one whose key is `prefix`, as
+    /// stale and refetches those that have been loaded before.
+    pub fn invalidate(&self, prefix: &str) {
+        let name = format!("{prefix}:");
+        let to_refetch = self
+            .entries
+            .try_update_value(|entries| {
+                entries
+                    .iter_mut()
+                    .filter(|(key, _)| key.as_str() == prefix || key.as_str().starts_with(&name))
+                    .filter_map(|(key, entry)| {
+                        entry.stale = true;
+                        Some((key.clone(), entry.loader.clone()?))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        for (key, loader) in to_refetch {
+            self.revalidate(&key, loader);
+        }
+    }
+}
+
+/// A resource backed by the query cache.
+///
+/// `fetcher` only runs for arguments that aren't cached yet or whose cached
+/// value went stale; identical requests made at the same time share one
+/// fetch. Queries sharing a `name` must return the same type.
+///
+/// The fetch's [`CancelToken`] is cancelled once no resource waits for it any
+/// more, say because the source changed before it finished; a cancelled
+/// fetch's result isn't cached.
+pub fn create_query<S, T, Fu>(
+    name: &'static str,
+    source: impl Fn() -> S + 'static,
+    fetcher: impl Fn(S, CancelToken) -> Fu + 'static,
+) -> Resource<(S, u64), T>
+where
+    S: Serialize + PartialEq + Clone + 'static,
+    T: Clone + 'static,
+    Fu: Future<Output = T> + 'static,
+{
+    let client = use_query_client();
+    let fetcher = Rc::new(fetcher);
+    // the key this resource is waiting on, and which of its fetches waits
+    let waiting = store_value(None::<(QueryKey, u64)>);
+    let fetches = store_value(0u64);
+    let stop_waiting = move |fetch: Option<u64>| {
+        let stopped = waiting
+            .try_update_value(|waiting| {
+                let current = waiting.as_ref().map(|(_, id)| *id);
+                if fetch.is_none_or(|fetch| current == Some(fetch)) {
+                    waiting.take()
+                } else {
+                    None
+                }
+            })
+            .flatten();
+        if let Some((key, _)) = stopped {
+            client.release(&key);
+        }
+    };
+    // the key this resource reads, kept in the cache while it does
+    let subscribed = store_value(None::<QueryKey>);
+    let subscribe = move |key: Option<QueryKey>| {
+        let previous = subscribed
+            .try_update_value(|subscribed| std::mem::replace(subscribed, key.clone()))
+            .flatten();
+        if previous == key {
+            return;
+        }
+        if let Some(key) = &key {
+            client.subscribe(key);
+        }
+        if let Some(previous) = previous {
+            client.unsubscribe(&previous);
+        }
+    };
+    on_cleanup(move || {
+        stop_waiting(None);
+        subscribe(None);
+    });
+    create_local_resource(
+        move || {
+            let args = source();
+            let key = QueryKey::new(name, &args);
+            subscribe(Some(key.clone()));
+            let version = client.version(&key).get();
+            (args, version)
+        },
+        move |(args, _)| {
+            let key = QueryKey::new(name, &args);
+            let fetcher = Rc::clone(&fetcher);
+            let loader: Loader = Rc::new(move |token| {
+                let fut = fetcher(args.clone(), token);
+                async move { Rc::new(fut.await) as Erased }.boxed_local()
+            });
+            stop_waiting(None);
+            let id = fetches
+                .try_update_value(|n| {
+                    *n += 1;
+                    *n
+                })
+                .unwrap_or_default();
+            let waits = waiting.try_update_value(|waiting| *waiting = Some((key.clone(), id)));
+            if waits.is_some() {
+                client.wait(&key);
+            }
+            async move {
+                let value = client.fetch::<T>(key, loader).await;
+                stop_waiting(Some(id));
+                value
+            }
+        },
+    )
+}
+
+/// The arguments of a command, plus the request id it can be cancelled by.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancellableArgs<'a, A> {
+    #[serde(flatten)]
+    args: &'a A,
+    request_id: &'a str,
+}
+
+/// [`create_query`] for a backend command, keyed by the command name and
+/// retried according to its [`CallPolicy`].
+pub fn create_command_query<A, R>(
+    cmd: &'static str,
+    args: impl Fn() -> A + 'static,
+) -> Resource<(A, u64), Result<R, IpcError>>
+where
+    A: Serialize + PartialEq + Clone + 'static,
+    R: DeserializeOwned + Clone + 'static,
+{
+    create_command_query_with_attempts(cmd, args).0
+}
+
+/// [`create_command_query`], also returning the attempt number of the latest
+/// fetch for its current arguments.
+///
+/// Every attempt sends its own `requestId`, so commands that take one stop
+/// working on a fetch nobody waits for any more.
+pub fn create_command_query_with_attempts<A, R>(
+    cmd: &'static str,
+    args: impl Fn() -> A + 'static,
+) -> (Resource<(A, u64), Result<R, IpcError>>, Signal<u32>)
+where
+    A: Serialize + PartialEq + Clone + 'static,
+    R: DeserializeOwned + Clone + 'static,
+{
+    let client = use_query_client();
+    let args = Rc::new(args);
+    let source = Rc::clone(&args);
+    let resource = create_query(cmd, move || source(), move |args, token| async move {
+        // kept with the cache entry, as background refetches can outlive the
+        // component
+        let attempts = client.attempts(&QueryKey::new(cmd, &args));
+        let policy = CallPolicy::for_command(cmd);
+        retry(policy, attempts, &token, |attempt| {
+            let args = args.clone();
+            async move {
+                let args = CancellableArgs {
+                    args: &args,
+                    request_id: attempt.request_id(),
+                };
+                ipc::call(cmd, &args).await
+            }
+        })
+        .await
+    });
+    let attempts = Signal::derive(move || client.attempts(&QueryKey::new(cmd, &args())).get());
+    (resource, attempts)
+}
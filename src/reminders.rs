@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{use_can, Capability};
+use crate::ipc::{self, NoArgs};
+use crate::toast::{use_toasts, ToastAction, ToastLevel};
+use crate::todos::format_due;
+
+const SNOOZE_MINUTES: u32 = 10;
+
+/// Mirrors the backend's reminder keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReminderKey {
+    pub todo_id: Uuid,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct Reminder {
+    pub key: ReminderKey,
+    /// `key.at`, or later if snoozed.
+    pub fires_at: DateTime<Utc>,
+    pub text: String,
+    pub due: DateTime<Utc>,
+    /// Fired, and waiting to be snoozed or dismissed.
+    pub fired: bool,
+}
+
+#[derive(Serialize)]
+struct SnoozeArgs {
+    key: ReminderKey,
+    minutes: u32,
+}
+
+#[derive(Serialize)]
+struct DismissArgs {
+    key: ReminderKey,
+}
+
+/// Shows every reminder the backend fires as a toast that stays up until
+/// it's snoozed or dismissed, in this window or any other.
+///
+/// Reminders that fired before this window was listening, such as those due
+/// when the app starts, are picked up from `list_reminders` on mount.
+#[component]
+pub fn ReminderHost() -> impl IntoView {
+    let toasts = use_toasts();
+    let can_write = use_can(Capability::TodoWrite);
+    // the toast showing each reminder
+    let shown = store_value(HashMap::<ReminderKey, Uuid>::new());
+
+    let snooze = create_action(move |key: &ReminderKey| {
+        let args = SnoozeArgs {
+            key: *key,
+            minutes: SNOOZE_MINUTES,
+        };
+        async move {
+            if let Err(err) = ipc::call::<_, ()>("snooze_reminder", &args).await {
+                toasts.error(format!("Couldn't snooze the reminder: {err}"));
+            }
+        }
+    });
+    let dismiss = create_action(move |key: &ReminderKey| {
+        let args = DismissArgs { key: *key };
+        async move {
+            if let Err(err) = ipc::call::<_, ()>("dismiss_reminder", &args).await {
+                toasts.error(format!("Couldn't dismiss the reminder: {err}"));
+            }
+        }
+    });
+
+    let show = move |reminder: Reminder| {
+        let key = reminder.key;
+        // fired while the list was loading
+        if shown.with_value(|shown| shown.contains_key(&key)) {
+            return;
+        }
+        let message = format!("Reminder: {} (due {})", reminder.text, format_due(reminder.due));
+        // read-only windows can see reminders but not act on them
+        let actions = if can_write.get_untracked() {
+            vec![
+                ToastAction::new(format!("Snooze {SNOOZE_MINUTES} min"), move || {
+                    snooze.dispatch(key)
+                }),
+                ToastAction::new("Dismiss", move || dismiss.dispatch(key)),
+            ]
+        } else {
+            Vec::new()
+        };
+        let id = toasts.persist(ToastLevel::Info, message, actions);
+        shown.update_value(|shown| {
+            shown.insert(key, id);
+        });
+    };
+    ipc::listen("reminder://due", show);
+    spawn_local(async move {
+        match ipc::call::<_, Vec<Reminder>>("list_reminders", &NoArgs {}).await {
+            Ok(reminders) => reminders.into_iter().filter(|r| r.fired).for_each(show),
+            Err(err) => log::warn!("failed to load fired reminders: {err}"),
+        }
+    });
+    // handled in another window, or by the buttons above
+    ipc::listen("reminder://handled", move |key: ReminderKey| {
+        if let Some(id) = shown.try_update_value(|shown| shown.remove(&key)).flatten() {
+            toasts.dismiss(id);
+        }
+    });
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use leptos::*;
+use leptos_router::A;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::debounce::create_debounced;
+use crate::query::create_command_query;
+
+/// How long typing has to pause before searching.
+const SEARCH_DELAY: Duration = Duration::from_millis(200);
+const RESULT_LIMIT: usize = 20;
+
+/// Mirrors the backend's search kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Kind {
+    Todo,
+    Note,
+}
+
+/// Text with the byte ranges that matched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+struct Snippet {
+    text: String,
+    highlights: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Hit {
+    id: Uuid,
+    kind: Kind,
+    title: Snippet,
+    body: Snippet,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct SearchFilters {
+    /// All kinds if empty.
+    kinds: Vec<Kind>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct SearchArgs {
+    query: String,
+    filters: SearchFilters,
+    limit: usize,
+}
+
+/// The snippet's text with its matches marked.
+fn highlighted(snippet: Snippet) -> View {
+    let Snippet { text, highlights } = snippet;
+    let mut parts = Vec::new();
+    let mut at = 0;
+    for (start, end) in highlights {
+        // skip ranges that don't fall on character boundaries
+        let (Some(before), Some(matched)) = (text.get(at..start), text.get(start..end)) else {
+            continue;
+        };
+        parts.push(before.to_owned().into_view());
+        parts.push(view! { <mark>{matched.to_owned()}</mark> }.into_view());
+        at = end;
+    }
+    parts.push(text.get(at..).unwrap_or_default().to_owned().into_view());
+    parts.collect_view()
+}
+
+/// Searches todos and notes as you type.
+#[component]
+pub fn SearchPage() -> impl IntoView {
+    let query = create_rw_signal(String::new());
+    let kind = create_rw_signal(None::<Kind>);
+    let debounced = create_debounced(query, SEARCH_DELAY);
+    let results = create_command_query::<_, Vec<Hit>>("search", move || SearchArgs {
+        query: debounced.get(),
+        filters: SearchFilters {
+            kinds: kind.get().into_iter().collect(),
+        },
+        limit: RESULT_LIMIT,
+    });
+    let hits = move || {
+        results
+            .get()
+            .and_then(Result::ok)
+            .unwrap_or_default()
+    };
+
+    view! {
+        <section class="search">
+            <div class="row">
+                <input
+                    type="search"
+                    placeholder="Search todos and notes..."
+                    aria-label="Search"
+                    prop:value=query
+                    on:input=move |ev| query.set(event_target_value(&ev))
+                />
+                <select
+                    aria-label="Kind"
+                    on:change=move |ev| {
+                        kind.set(
+                            match event_target_value(&ev).as_str() {
+                                "todo" => Some(Kind::Todo),
+                                "note" => Some(Kind::Note),
+                                _ => None,
+                            },
+                        )
+                    }
+                >
+                    <option value="all">"Everything"</option>
+                    <option value="todo">"Todos"</option>
+                    <option value="note">"Notes"</option>
+                </select>
+            </div>
+            <Show
+                when=move || debounced.with(|q| q.trim().is_empty()) || !hits().is_empty()
+                fallback=|| view! { <p>"No matches."</p> }
+            >
+                <ul class="search-results">
+                    <For
+                        each=hits
+                        // the same hit matches differently as the query changes
+                        key=|hit| (hit.id, hit.title.clone(), hit.body.clone())
+                        children=|hit| {
+                            let (href, label) = match hit.kind {
+                                Kind::Todo => ("/".to_owned(), "Todo"),
+                                Kind::Note => (format!("/notes?id={}", hit.id), "Note"),
+                            };
+                            view! {
+                                <li>
+                                    <span class="search-kind">{label}</span>
+                                    <A href=href>{highlighted(hit.title)}</A>
+                                    <p>{highlighted(hit.body)}</p>
+                                </li>
+                            }
+                        }
+                    />
+
+                </ul>
+            </Show>
+        </section>
+    }
+}
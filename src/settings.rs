@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ipc::{self, NoArgs};
+use crate::kanban::KanbanColumn;
+use crate::query::{create_command_query, use_query_client, QueryClient};
+use crate::toast::{use_toasts, Toasts};
+
+/// Mirrors the backend's settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Shortcut overrides by command id; `None` unbinds the command.
+    pub shortcuts: BTreeMap<String, Option<String>>,
+    pub close_to_tray: bool,
+    /// Empty for one column per status.
+    pub kanban_columns: Vec<KanbanColumn>,
+}
+
+#[derive(Serialize)]
+struct UpdateSettingsArgs<'a> {
+    settings: &'a Settings,
+}
+
+/// The settings saved by the backend, kept in sync across windows.
+///
+/// Changes show up right away and are then saved; if saving fails, the saved
+/// settings are loaded again.
+#[derive(Clone, Copy)]
+pub struct UserSettings {
+    settings: RwSignal<Settings>,
+    toasts: Toasts,
+    query_client: QueryClient,
+}
+
+pub fn provide_settings() {
+    let settings = create_rw_signal(Settings::default());
+    let saved = create_command_query::<_, Settings>("get_settings", || NoArgs {});
+    create_effect(move |_| {
+        if let Some(Ok(saved)) = saved.get() {
+            settings.set(saved);
+        }
+    });
+    provide_context(UserSettings {
+        settings,
+        toasts: use_toasts(),
+        query_client: use_query_client(),
+    });
+}
+
+pub fn use_settings() -> UserSettings {
+    expect_context::<UserSettings>()
+}
+
+impl UserSettings {
+    pub fn get(&self) -> Signal<Settings> {
+        self.settings.into()
+    }
+
+    pub fn update(&self, change: impl FnOnce(&mut Settings)) {
+        self.settings.update(change);
+        let settings = self.settings.get_untracked();
+        let UserSettings {
+            toasts,
+            query_client,
+            ..
+        } = *self;
+        spawn_local(async move {
+            let args = UpdateSettingsArgs {
+                settings: &settings,
+            };
+            if let Err(err) = ipc::call::<_, ()>("update_settings", &args).await {
+                toasts.error(format!("Settings couldn't be saved: {err}"));
+                query_client.invalidate("get_settings");
+            }
+        });
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use leptos::ev::KeyboardEvent;
+use leptos::html::ElementDescriptor;
+use leptos::*;
+use leptos_router::use_location;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement};
+
+use crate::commands::{register_command, use_commands, Command};
+use crate::modal::use_modals;
+use crate::settings::{use_settings, UserSettings};
+
+/// A key with modifiers, written like `Ctrl+Shift+K`.
+///
+/// Cmd counts as Ctrl, so bindings work the same on macOS.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    /// `KeyboardEvent.key`, lowercased if it is a single character.
+    key: String,
+}
+
+impl Chord {
+    fn new(ctrl: bool, alt: bool, shift: bool, key: &str) -> Self {
+        let key = if key.chars().count() == 1 {
+            key.to_lowercase()
+        } else {
+            key.to_owned()
+        };
+        Self {
+            ctrl,
+            alt,
+            shift,
+            key,
+        }
+    }
+
+    /// `None` for presses of a modifier on its own.
+    pub fn from_event(ev: &KeyboardEvent) -> Option<Self> {
+        let key = ev.key();
+        if matches!(key.as_str(), "Control" | "Alt" | "Shift" | "Meta") {
+            return None;
+        }
+        Some(Self::new(
+            ev.ctrl_key() || ev.meta_key(),
+            ev.alt_key(),
+            ev.shift_key(),
+            &key,
+        ))
+    }
+
+    /// Without Ctrl or Alt the chord would also be typed into text fields.
+    fn has_modifier(&self) -> bool {
+        self.ctrl || self.alt
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, key) = match s.rsplit_once('+') {
+            // `Ctrl++` binds the plus key
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", s),
+        };
+        if key.is_empty() {
+            return Err(format!("no key in shortcut {s:?}"));
+        }
+        let (mut ctrl, mut alt, mut shift) = (false, false, false);
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "meta" => ctrl = true,
+                "alt" | "option" => alt = true,
+                "shift" => shift = true,
+                _ => return Err(format!("unknown modifier {modifier:?} in shortcut {s:?}")),
+            }
+        }
+        Ok(Self::new(ctrl, alt, shift, key))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        if self.key.chars().count() == 1 {
+            f.write_str(&self.key.to_uppercase())
+        } else {
+            f.write_str(&self.key)
+        }
+    }
+}
+
+/// Shown on the rebinding buttons of the cheat sheet.
+const REBIND_HINT: &str = "Click, then press the new shortcut. Backspace unbinds, Escape cancels.";
+
+/// Where a shortcut applies. When several active bindings share a chord, the
+/// most specific scope wins.
+#[derive(Clone)]
+pub enum Scope {
+    Global,
+    /// While the current path starts with this.
+    Route(&'static str),
+    /// While focus is inside a component's element.
+    Component {
+        name: String,
+        element: Rc<dyn Fn() -> Option<Element>>,
+    },
+}
+
+impl Scope {
+    pub fn component<T>(name: impl Into<String>, node_ref: NodeRef<T>) -> Self
+    where
+        T: ElementDescriptor + Clone + 'static,
+    {
+        let element = move || {
+            node_ref
+                .get_untracked()
+                .map(|el| Element::from((*el.into_any()).clone()))
+        };
+        Scope::Component {
+            name: name.into(),
+            element: Rc::new(element),
+        }
+    }
+
+    /// Also what conflicting bindings are grouped by.
+    fn label(&self) -> String {
+        match self {
+            Scope::Global => "Global".to_owned(),
+            Scope::Route(path) => format!("On {path}"),
+            Scope::Component { name, .. } => format!("In {name}"),
+        }
+    }
+
+    fn specificity(&self) -> usize {
+        match self {
+            Scope::Global => 0,
+            Scope::Route(path) => 1 + path.len(),
+            Scope::Component { .. } => usize::MAX,
+        }
+    }
+
+    fn is_active(&self, path: &str) -> bool {
+        match self {
+            Scope::Global => true,
+            Scope::Route(prefix) => path.starts_with(prefix),
+            Scope::Component { element, .. } => {
+                let Some(element) = element() else {
+                    return false;
+                };
+                document()
+                    .active_element()
+                    .is_some_and(|active| element.contains(Some(active.as_ref())))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Binding {
+    key: u64,
+    command: String,
+    default: Chord,
+    scope: Scope,
+}
+
+/// Keyboard shortcuts for registered commands.
+///
+/// Each command declares a default chord and a scope; users can rebind or
+/// unbind it, and their overrides are kept in the settings.
+#[derive(Clone, Copy)]
+pub struct Shortcuts {
+    bindings: RwSignal<Vec<Binding>>,
+    next_key: StoredValue<u64>,
+    path: RwSignal<String>,
+    settings: UserSettings,
+    /// Chords the native menu handles as accelerators.
+    menu_chords: StoredValue<HashSet<Chord>>,
+}
+
+pub fn provide_shortcuts() {
+    provide_context(Shortcuts {
+        bindings: create_rw_signal(Vec::new()),
+        next_key: store_value(0),
+        path: create_rw_signal(String::new()),
+        settings: use_settings(),
+        menu_chords: store_value(HashSet::new()),
+    });
+}
+
+pub fn use_shortcuts() -> Shortcuts {
+    expect_context::<Shortcuts>()
+}
+
+/// Binds `chord` to the command with id `command` until the current reactive
+/// scope is disposed.
+///
+/// # Panics
+///
+/// If `chord` can't be parsed; defaults are written by hand.
+pub fn register_shortcut(command: impl Into<String>, chord: &str, scope: Scope) {
+    let shortcuts = use_shortcuts();
+    let default = chord
+        .parse::<Chord>()
+        .unwrap_or_else(|err| panic!("invalid default shortcut: {err}"));
+    let key = shortcuts.next_key.get_value();
+    shortcuts.next_key.set_value(key + 1);
+    shortcuts.bindings.update(|bindings| {
+        bindings.push(Binding {
+            key,
+            command: command.into(),
+            default,
+            scope,
+        })
+    });
+    on_cleanup(move || {
+        shortcuts
+            .bindings
+            .try_update(|bindings| bindings.retain(|binding| binding.key != key));
+    });
+}
+
+/// The chord a binding uses once the user's overrides are applied.
+fn effective(binding: &Binding, overrides: &BTreeMap<String, Option<String>>) -> Option<Chord> {
+    match overrides.get(&binding.command) {
+        Some(Some(chord)) => chord.parse().ok(),
+        Some(None) => None,
+        None => Some(binding.default.clone()),
+    }
+}
+
+impl Shortcuts {
+    /// Commands whose chords collide with another command's in the same
+    /// scope.
+    pub fn conflicts(&self) -> Signal<HashSet<String>> {
+        let bindings = self.bindings;
+        let settings = self.settings.get();
+        Signal::derive(move || {
+            let mut by_chord = HashMap::<(Chord, String), HashSet<String>>::new();
+            settings.with(|settings| {
+                bindings.with(|bindings| {
+                    for binding in bindings {
+                        if let Some(chord) = effective(binding, &settings.shortcuts) {
+                            by_chord
+                                .entry((chord, binding.scope.label()))
+                                .or_default()
+                                .insert(binding.command.clone());
+                        }
+                    }
+                })
+            });
+            by_chord
+                .into_values()
+                .filter(|commands| commands.len() > 1)
+                .flatten()
+                .collect()
+        })
+    }
+
+    /// Leaves key presses of `chords` to the native menu, whose accelerators
+    /// run their commands, so they don't run twice.
+    pub fn leave_to_menu(&self, chords: HashSet<Chord>) {
+        self.menu_chords.set_value(chords);
+    }
+
+    /// The bindings whose scope applies right now.
+    fn active(&self) -> Vec<Binding> {
+        let path = self.path.get_untracked();
+        self.bindings.with_untracked(|bindings| {
+            bindings
+                .iter()
+                .filter(|binding| binding.scope.is_active(&path))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// The command `chord` runs right now, if any: the one bound in the most
+    /// specific scope, and among those the latest registered.
+    fn resolve(&self, chord: &Chord) -> Option<String> {
+        self.settings.get().with_untracked(|settings| {
+            self.active()
+                .into_iter()
+                .filter(|binding| effective(binding, &settings.shortcuts).as_ref() == Some(chord))
+                .max_by_key(|binding| (binding.scope.specificity(), binding.key))
+                .map(|binding| binding.command)
+        })
+    }
+}
+
+fn is_typing(ev: &KeyboardEvent) -> bool {
+    let Some(target) = ev.target().and_then(|t| t.dyn_into::<HtmlElement>().ok()) else {
+        return false;
+    };
+    matches!(target.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || target.is_content_editable()
+}
+
+/// Runs commands for their shortcuts, and registers the cheat sheet. Must be
+/// rendered inside the router.
+#[component]
+pub fn ShortcutHost() -> impl IntoView {
+    let shortcuts = use_shortcuts();
+    let commands = use_commands();
+    let modals = use_modals();
+    let location = use_location();
+    create_effect(move |_| shortcuts.path.set(location.pathname.get()));
+    let conflicts = shortcuts.conflicts();
+    create_effect(move |_| {
+        conflicts.with(|conflicts| {
+            if !conflicts.is_empty() {
+                log::warn!("shortcuts conflict for commands {conflicts:?}");
+            }
+        })
+    });
+
+    let keydown = window_event_listener(ev::keydown, move |ev| {
+        let Some(chord) = Chord::from_event(&ev) else {
+            return;
+        };
+        if is_typing(&ev) && !chord.has_modifier() {
+            return;
+        }
+        if shortcuts.menu_chords.with_value(|chords| chords.contains(&chord)) {
+            return;
+        }
+        if let Some(command) = shortcuts.resolve(&chord) {
+            if commands.run(&command) {
+                ev.prevent_default();
+            }
+        }
+    });
+    on_cleanup(move || keydown.remove());
+
+    register_command(Command::new("shortcuts.show", "Show keyboard shortcuts", move || {
+        // scopes depend on focus, which the overlay is about to take
+        let active = shortcuts
+            .active()
+            .into_iter()
+            .map(|binding| binding.key)
+            .collect::<HashSet<_>>();
+        modals.open(move |_| view! { <CheatSheet active=active.clone()/> }, || {});
+    }));
+    register_shortcut("shortcuts.show", "Shift+?", Scope::Global);
+}
+
+/// Lists the active bindings, and lets each be rebound.
+#[component]
+fn CheatSheet(active: HashSet<u64>) -> impl IntoView {
+    let shortcuts = use_shortcuts();
+    let commands = use_commands().list();
+    let settings = shortcuts.settings;
+    let conflicts = shortcuts.conflicts();
+    let recording = create_rw_signal(None::<String>);
+
+    let rows = move || {
+        let overrides = settings.get().with(|s| s.shortcuts.clone());
+        let titles = commands.with(|commands| {
+            commands
+                .iter()
+                .map(|command| (command.id.clone(), command.title.clone()))
+                .collect::<HashMap<_, _>>()
+        });
+        shortcuts.bindings.with(|bindings| {
+            bindings
+                .iter()
+                .filter(|binding| active.contains(&binding.key))
+                .map(|binding| {
+                    let title = titles.get(&binding.command).cloned();
+                    let chord = effective(binding, &overrides);
+                    let overridden = overrides.contains_key(&binding.command);
+                    (binding.clone(), title, chord, overridden)
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let record = move |command: String, ev: KeyboardEvent| {
+        // keep the modal and the shortcuts from seeing the keys being recorded
+        ev.stop_propagation();
+        ev.prevent_default();
+        let chord = match ev.key().as_str() {
+            "Escape" => None,
+            "Backspace" | "Delete" => Some(None),
+            _ => match Chord::from_event(&ev) {
+                Some(chord) => Some(Some(chord.to_string())),
+                None => return,
+            },
+        };
+        if let Some(chord) = chord {
+            settings.update(|s| {
+                s.shortcuts.insert(command, chord);
+            });
+        }
+        recording.set(None);
+    };
+
+    view! {
+        <h2>"Keyboard shortcuts"</h2>
+        <table class="shortcuts">
+            {move || {
+                rows()
+                    .into_iter()
+                    .map(|(binding, title, chord, overridden)| {
+                        let command = binding.command;
+                        let conflict = conflicts.with(|c| c.contains(&command));
+                        let is_recording = {
+                            let command = command.clone();
+                            move || recording.with(|r| r.as_deref() == Some(command.as_str()))
+                        };
+                        let shown = chord.map_or_else(|| "unbound".to_owned(), |c| c.to_string());
+                        let label = {
+                            let is_recording = is_recording.clone();
+                            move || {
+                                if is_recording() {
+                                    "Press keys...".to_owned()
+                                } else {
+                                    shown.clone()
+                                }
+                            }
+                        };
+                        let start_recording = {
+                            let command = command.clone();
+                            move |_| recording.set(Some(command.clone()))
+                        };
+                        let on_keydown = {
+                            let command = command.clone();
+                            move |ev: KeyboardEvent| {
+                                if is_recording() {
+                                    record(command.clone(), ev);
+                                }
+                            }
+                        };
+                        let reset = overridden.then(|| {
+                            let command = command.clone();
+                            let reset = move |_| {
+                                settings.update(|s| {
+                                    s.shortcuts.remove(&command);
+                                })
+                            };
+                            view! { <button on:click=reset>"Reset"</button> }
+                        });
+                        view! {
+                            <tr class:conflict=conflict>
+                                <td>{title.unwrap_or(command)}</td>
+                                <td class="scope">{binding.scope.label()}</td>
+                                <td>
+                                    // undelegated, so stopping propagation keeps the
+                                    // keys from the window's listeners
+                                    <button
+                                        class="chord"
+                                        title=REBIND_HINT
+                                        on:click=start_recording
+                                        on:keydown:undelegated=on_keydown
+                                    >
+                                        {label}
+                                    </button>
+                                </td>
+                                <td>
+                                    {reset}
+                                    {conflict
+                                        .then(|| view! { <span class="error">"conflict"</span> })}
+                                </td>
+                            </tr>
+                        }
+                    })
+                    .collect_view()
+            }}
+        </table>
+    }
+}
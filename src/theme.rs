@@ -0,0 +1,70 @@
+use leptos::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    fn name(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    fn system() -> Self {
+        let dark = window()
+            .match_media("(prefers-color-scheme: dark)")
+            .ok()
+            .flatten()
+            .is_some_and(|query| query.matches());
+        if dark {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+}
+
+/// The color theme, applied as `data-theme` on the root element. Starts out
+/// following the system's preference.
+#[derive(Clone, Copy)]
+pub struct Appearance {
+    theme: RwSignal<Theme>,
+}
+
+pub fn provide_appearance() {
+    let appearance = Appearance {
+        theme: create_rw_signal(Theme::system()),
+    };
+    create_effect(move |_| {
+        let theme = appearance.theme.get();
+        if let Some(root) = document().document_element() {
+            _ = root.set_attribute("data-theme", theme.name());
+        }
+    });
+    provide_context(appearance);
+}
+
+pub fn use_appearance() -> Appearance {
+    expect_context::<Appearance>()
+}
+
+impl Appearance {
+    pub fn theme(&self) -> Signal<Theme> {
+        self.theme.into()
+    }
+
+    pub fn toggle_theme(&self) {
+        self.theme.update(|theme| *theme = theme.toggled());
+    }
+}
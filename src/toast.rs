@@ -0,0 +1,297 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use wasm_bindgen::JsValue;
+
+use crate::auth::{use_can, Capability};
+use crate::ipc::{self, NoArgs};
+use crate::query::create_command_query;
+
+/// How long a toast stays up before it dismisses itself.
+const TOAST_TIMEOUT: Duration = Duration::from_secs(4);
+/// Toasts with an action stay up longer, so there's time to click it.
+const ACTION_TOAST_TIMEOUT: Duration = Duration::from_secs(8);
+/// How many toasts are stacked at once; the oldest make room for new ones.
+const MAX_VISIBLE: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn name(self) -> &'static str {
+        match self {
+            ToastLevel::Info => "info",
+            ToastLevel::Success => "success",
+            ToastLevel::Warning => "warning",
+            ToastLevel::Error => "error",
+        }
+    }
+}
+
+/// A button on a toast, like Undo or Retry. Clicking it also dismisses the
+/// toast.
+#[derive(Clone)]
+pub struct ToastAction {
+    label: String,
+    run: Rc<dyn Fn()>,
+}
+
+impl ToastAction {
+    pub fn new(label: impl Into<String>, run: impl Fn() + 'static) -> Self {
+        Self {
+            label: label.into(),
+            run: Rc::new(run),
+        }
+    }
+
+    pub fn undo(run: impl Fn() + 'static) -> Self {
+        Self::new("Undo", run)
+    }
+
+    pub fn retry(run: impl Fn() + 'static) -> Self {
+        Self::new("Retry", run)
+    }
+}
+
+/// A notification as kept in the backend's history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub level: ToastLevel,
+    pub message: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: f64,
+}
+
+#[derive(Clone)]
+struct Toast {
+    notification: Notification,
+    actions: Vec<ToastAction>,
+    /// Doesn't time out.
+    persistent: bool,
+}
+
+#[derive(Serialize)]
+struct RecordNotificationArgs<'a> {
+    notification: &'a Notification,
+}
+
+/// Short-lived messages stacked in a corner of the window.
+///
+/// Every notification is also recorded in the backend's history, which
+/// [`NotificationHistory`] shows.
+#[derive(Clone, Copy)]
+pub struct Toasts {
+    toasts: RwSignal<Vec<Toast>>,
+}
+
+pub fn provide_toasts() {
+    provide_context(Toasts {
+        toasts: create_rw_signal(Vec::new()),
+    });
+}
+
+pub fn use_toasts() -> Toasts {
+    expect_context::<Toasts>()
+}
+
+impl Toasts {
+    pub fn notify(
+        &self,
+        level: ToastLevel,
+        message: impl Into<String>,
+        action: Option<ToastAction>,
+    ) {
+        let timeout = if action.is_some() {
+            ACTION_TOAST_TIMEOUT
+        } else {
+            TOAST_TIMEOUT
+        };
+        self.show(level, message.into(), action.into_iter().collect(), Some(timeout));
+    }
+
+    /// Like [`Toasts::notify`], but stays up until it's closed or one of its
+    /// `actions` is clicked. Returns its id, for [`Toasts::dismiss`].
+    pub fn persist(
+        &self,
+        level: ToastLevel,
+        message: impl Into<String>,
+        actions: Vec<ToastAction>,
+    ) -> Uuid {
+        self.show(level, message.into(), actions, None)
+    }
+
+    fn show(
+        &self,
+        level: ToastLevel,
+        message: String,
+        actions: Vec<ToastAction>,
+        timeout: Option<Duration>,
+    ) -> Uuid {
+        let notification = Notification {
+            id: Uuid::new_v4(),
+            level,
+            message,
+            created_at: js_sys::Date::now(),
+        };
+        let id = notification.id;
+        spawn_local({
+            let notification = notification.clone();
+            async move {
+                let args = RecordNotificationArgs {
+                    notification: &notification,
+                };
+                // only logged, so a broken history can't set off more toasts
+                if let Err(err) = ipc::call::<_, ()>("record_notification", &args).await {
+                    log::warn!("failed to record notification: {err}");
+                }
+            }
+        });
+        self.toasts.update(|toasts| {
+            toasts.push(Toast {
+                notification,
+                actions,
+                persistent: timeout.is_none(),
+            });
+            // persistent toasts only make room once no others are left
+            while toasts.len() > MAX_VISIBLE {
+                let oldest = toasts.iter().position(|toast| !toast.persistent);
+                toasts.remove(oldest.unwrap_or(0));
+            }
+        });
+        if let Some(timeout) = timeout {
+            let this = *self;
+            set_timeout(move || this.dismiss(id), timeout);
+        }
+        id
+    }
+
+    pub fn error(&self, message: impl Into<String>) {
+        self.notify(ToastLevel::Error, message, None);
+    }
+
+    pub fn dismiss(&self, id: Uuid) {
+        self.toasts
+            .try_update(|toasts| toasts.retain(|toast| toast.notification.id != id));
+    }
+}
+
+#[component]
+pub fn ToastHost() -> impl IntoView {
+    let toasts = use_toasts();
+    view! {
+        <div class="toasts" role="status" aria-live="polite">
+            <For
+                each=move || toasts.toasts.get()
+                key=|toast| toast.notification.id
+                children=move |toast| {
+                    let Notification { id, level, message, .. } = toast.notification;
+                    let actions = toast
+                        .actions
+                        .into_iter()
+                        .map(|ToastAction { label, run }| {
+                            view! {
+                                <button
+                                    class="toast-action"
+                                    on:click=move |_| {
+                                        run();
+                                        toasts.dismiss(id);
+                                    }
+                                >
+                                    {label}
+                                </button>
+                            }
+                        })
+                        .collect_view();
+                    view! {
+                        <div class=format!("toast {}", level.name())>
+                            <span>{message}</span>
+                            {actions}
+                            <button on:click=move |_| toasts.dismiss(id)>"×"</button>
+                        </div>
+                    }
+                }
+            />
+
+        </div>
+    }
+}
+
+/// A timestamp in milliseconds since the Unix epoch, in the user's locale.
+pub fn format_time(millis: f64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(millis))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into()
+}
+
+/// A toggleable panel listing past notifications, newest first.
+#[component]
+pub fn NotificationHistory() -> impl IntoView {
+    let (open, set_open) = create_signal(false);
+    view! {
+        <button
+            class="notification-toggle"
+            on:click=move |_| set_open.update(|open| *open = !*open)
+        >
+            "Notifications"
+        </button>
+        <Show when=move || open.get()>
+            <NotificationList/>
+        </Show>
+    }
+}
+
+/// Only mounted while the panel is open, so closed panels don't refetch the
+/// history on every notification.
+#[component]
+fn NotificationList() -> impl IntoView {
+    let history = create_command_query::<_, Vec<Notification>>("list_notifications", || NoArgs {});
+    let entries = move || {
+        let mut entries = history.get().and_then(Result::ok).unwrap_or_default();
+        entries.reverse();
+        entries
+    };
+    let can_clear = use_can(Capability::NotifyClear);
+    let clear = create_action(|_: &()| async move {
+        if let Err(err) = ipc::call::<_, ()>("clear_notifications", &NoArgs {}).await {
+            log::warn!("failed to clear notifications: {err}");
+        }
+    });
+    view! {
+        <section class="notification-history">
+            <header>
+                <h2>"Notifications"</h2>
+                <Show when=move || can_clear.get()>
+                    <button on:click=move |_| clear.dispatch(())>"Clear"</button>
+                </Show>
+            </header>
+            <Show when=move || !entries().is_empty() fallback=|| view! { <p>"Nothing yet."</p> }>
+                <ul>
+                    <For
+                        each=entries
+                        key=|notification| notification.id
+                        children=|notification| {
+                            view! {
+                                <li class=format!("notification {}", notification.level.name())>
+                                    <time>{format_time(notification.created_at)}</time>
+                                    " "
+                                    {notification.message}
+                                </li>
+                            }
+                        }
+                    />
+
+                </ul>
+            </Show>
+        </section>
+    }
+}
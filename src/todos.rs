@@ -0,0 +1,882 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Days, Local, NaiveDateTime, Utc};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::{use_can, Capability};
+use crate::history::{use_history, History};
+use crate::ipc::{IpcError, NoArgs};
+use crate::modal::{use_modals, ModalHandle};
+use crate::outbox::{use_outbox, Mutation, Outbox};
+use crate::query::create_command_query;
+use crate::toast::{use_toasts, ToastAction, ToastLevel, Toasts};
+
+/// The format of `<input type="datetime-local">` values.
+const INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+/// The editor's reminder choices, in minutes before the due date.
+const REMINDERS: [(Option<u32>, &str); 6] = [
+    (None, "No reminder"),
+    (Some(0), "When due"),
+    (Some(5), "5 minutes before"),
+    (Some(15), "15 minutes before"),
+    (Some(60), "1 hour before"),
+    (Some(24 * 60), "1 day before"),
+];
+/// The editor's repeat choices, with their labels.
+const REPEATS: [(&str, &str); 4] = [
+    ("none", "Never"),
+    ("daily", "Daily"),
+    ("weekly", "Weekly"),
+    ("rule", "Custom"),
+];
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    /// Most important first.
+    const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Normal => "Normal",
+            Priority::High => "High",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|priority| priority.name() == name)
+    }
+}
+
+/// Mirrors the backend's recurrence rules.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Rule { rrule: String },
+}
+
+impl Recurrence {
+    fn label(&self) -> String {
+        match self {
+            Recurrence::Daily => "Daily".to_owned(),
+            Recurrence::Weekly => "Weekly".to_owned(),
+            Recurrence::Rule { rrule } => rrule.clone(),
+        }
+    }
+}
+
+/// Mirrors the backend's board statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Open,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Open, Status::InProgress, Status::Done];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::InProgress => "in_progress",
+            Status::Done => "done",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Open => "To do",
+            Status::InProgress => "In progress",
+            Status::Done => "Done",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.name() == name)
+    }
+}
+
+/// Mirrors the backend's todo details.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TodoDetails {
+    pub due: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub notes: String,
+    pub recurrence: Option<Recurrence>,
+    /// Minutes before `due` to remind; no reminder if `None`.
+    pub remind_before: Option<u32>,
+    pub in_progress: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+pub struct Todo {
+    pub id: Uuid,
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+    #[serde(flatten)]
+    pub details: TodoDetails,
+    /// Still in the outbox; the backend hasn't applied it yet.
+    #[serde(skip)]
+    pub pending: bool,
+}
+
+impl Todo {
+    pub fn status(&self) -> Status {
+        if self.done {
+            Status::Done
+        } else if self.details.in_progress {
+            Status::InProgress
+        } else {
+            Status::Open
+        }
+    }
+}
+
+pub type TodosResource = Resource<(NoArgs, u64), Result<Vec<Todo>, IpcError>>;
+
+/// The saved todos, and the same with the changes still in the outbox
+/// applied on top.
+pub fn create_todos() -> (TodosResource, Signal<Vec<Todo>>) {
+    // refetched whenever any window changes the todos
+    let loaded = create_command_query::<_, Vec<Todo>>("list_todos", || NoArgs {});
+    let queued = use_outbox().pending();
+    let todos = Signal::derive(move || {
+        let mut todos = loaded.get().and_then(Result::ok).unwrap_or_default();
+        queued.with(|queued| {
+            for entry in queued {
+                apply_queued(&mut todos, &entry.mutation);
+            }
+        });
+        todos
+    });
+    (loaded, todos)
+}
+
+fn apply_queued(todos: &mut Vec<Todo>, mutation: &Mutation) {
+    let find = |todos: &mut Vec<Todo>, id: Uuid| {
+        todos.iter_mut().find(|todo| todo.id == id).map(|todo| {
+            todo.pending = true;
+            todo
+        })
+    };
+    match mutation {
+        Mutation::AddTodo { id, text, details } => {
+            if todos.iter().all(|todo| todo.id != *id) {
+                todos.push(Todo {
+                    id: *id,
+                    text: text.clone(),
+                    done: false,
+                    details: details.clone(),
+                    pending: true,
+                });
+            }
+        }
+        Mutation::UpdateTodo { id, text, details } => {
+            if let Some(todo) = find(todos, *id) {
+                todo.text = text.clone();
+                todo.details = details.clone();
+            }
+        }
+        // the next occurrence shows up once the backend has added it
+        Mutation::CompleteTodo { id, .. } => {
+            if let Some(todo) = find(todos, *id) {
+                todo.done = true;
+                todo.details.in_progress = false;
+            }
+        }
+        Mutation::ReopenTodo { id, next_id } => {
+            if let Some(todo) = find(todos, *id) {
+                todo.done = false;
+            }
+            todos.retain(|todo| todo.id != *next_id);
+        }
+        Mutation::SetTodoStatus {
+            id,
+            status,
+            next_id,
+        } => {
+            if let Some(todo) = find(todos, *id) {
+                todo.done = *status == Status::Done;
+                todo.details.in_progress = *status == Status::InProgress;
+            }
+            if *status != Status::Done {
+                todos.retain(|todo| todo.id != *next_id);
+            }
+        }
+        Mutation::DeleteTodo { id } => todos.retain(|todo| todo.id != *id),
+    }
+}
+
+/// Queues `change` and records it in the history, to be undone by queueing
+/// `inverse`. The toast announcing it offers the same undo.
+pub fn queue_undoable(
+    outbox: Outbox,
+    history: History,
+    toasts: Toasts,
+    level: ToastLevel,
+    message: &'static str,
+    change: Mutation,
+    inverse: Mutation,
+) {
+    outbox.queue(change.clone());
+    let entry = history.push(
+        move || outbox.queue(inverse.clone()),
+        move || outbox.queue(change.clone()),
+    );
+    let undo = ToastAction::undo(move || history.undo_entry(entry));
+    toasts.notify(level, message, Some(undo));
+}
+
+/// A value for `<input type="datetime-local">`, in local time.
+fn to_input(due: Option<DateTime<Utc>>) -> String {
+    due.map(|due| due.with_timezone(&Local).format(INPUT_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+/// `None` for an empty or invalid input.
+fn from_input(value: &str) -> Option<DateTime<Utc>> {
+    let local = NaiveDateTime::parse_from_str(value, INPUT_FORMAT).ok()?;
+    dates::resolve(local, &Local)
+}
+
+pub fn format_due(due: DateTime<Utc>) -> String {
+    due.with_timezone(&Local).format("%a %-d %b %H:%M").to_string()
+}
+
+/// When a todo is due, relative to now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueBucket {
+    Overdue,
+    Today,
+    /// Within the next seven days, after today.
+    ThisWeek,
+    Later,
+    NoDate,
+}
+
+impl DueBucket {
+    const ALL: [DueBucket; 5] = [
+        DueBucket::Overdue,
+        DueBucket::Today,
+        DueBucket::ThisWeek,
+        DueBucket::Later,
+        DueBucket::NoDate,
+    ];
+
+    fn of(due: Option<DateTime<Utc>>, now: DateTime<Local>) -> Self {
+        let Some(due) = due else {
+            return DueBucket::NoDate;
+        };
+        let day = dates::day_of(due, &Local);
+        let today = now.date_naive();
+        if due < now {
+            DueBucket::Overdue
+        } else if day == today {
+            DueBucket::Today
+        } else if today
+            .checked_add_days(Days::new(7))
+            .is_some_and(|week| day <= week)
+        {
+            DueBucket::ThisWeek
+        } else {
+            DueBucket::Later
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DueBucket::Overdue => "overdue",
+            DueBucket::Today => "today",
+            DueBucket::ThisWeek => "this_week",
+            DueBucket::Later => "later",
+            DueBucket::NoDate => "no_date",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DueBucket::Overdue => "Overdue",
+            DueBucket::Today => "Today",
+            DueBucket::ThisWeek => "This week",
+            DueBucket::Later => "Later",
+            DueBucket::NoDate => "No date",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|bucket| bucket.name() == name)
+    }
+}
+
+/// Which todos the list shows; `None` fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TodoFilter {
+    pub show_done: bool,
+    pub priority: Option<Priority>,
+    pub tag: Option<String>,
+    pub due: Option<DueBucket>,
+}
+
+impl TodoFilter {
+    fn matches(&self, todo: &Todo, now: DateTime<Local>) -> bool {
+        (self.show_done || !todo.done)
+            && self.priority.is_none_or(|p| todo.details.priority == p)
+            && self.tag.as_ref().is_none_or(|tag| todo.details.tags.contains(tag))
+            && self.due.is_none_or(|due| DueBucket::of(todo.details.due, now) == due)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    #[default]
+    Nothing,
+    Due,
+    Priority,
+    Tag,
+}
+
+impl GroupBy {
+    const ALL: [GroupBy; 4] = [GroupBy::Nothing, GroupBy::Due, GroupBy::Priority, GroupBy::Tag];
+
+    fn name(self) -> &'static str {
+        match self {
+            GroupBy::Nothing => "nothing",
+            GroupBy::Due => "due",
+            GroupBy::Priority => "priority",
+            GroupBy::Tag => "tag",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            GroupBy::Nothing => "Nothing",
+            GroupBy::Due => "Due date",
+            GroupBy::Priority => "Priority",
+            GroupBy::Tag => "Tag",
+        }
+    }
+}
+
+/// Labelled groups in display order, leaving out empty ones. A todo with
+/// several tags shows up under each of them.
+fn group(mut todos: Vec<Todo>, by: GroupBy, now: DateTime<Local>) -> Vec<(String, Vec<Todo>)> {
+    // soonest first, then most important; undated last
+    todos.sort_by(|a, b| {
+        let due = |todo: &Todo| (todo.details.due.is_none(), todo.details.due);
+        due(a)
+            .cmp(&due(b))
+            .then(b.details.priority.cmp(&a.details.priority))
+    });
+    let groups = match by {
+        GroupBy::Nothing => vec![(String::new(), todos)],
+        GroupBy::Due => DueBucket::ALL
+            .into_iter()
+            .map(|bucket| {
+                let todos = todos
+                    .iter()
+                    .filter(|todo| DueBucket::of(todo.details.due, now) == bucket)
+                    .cloned()
+                    .collect();
+                (bucket.label().to_owned(), todos)
+            })
+            .collect(),
+        GroupBy::Priority => Priority::ALL
+            .into_iter()
+            .map(|priority| {
+                let todos = todos
+                    .iter()
+                    .filter(|todo| todo.details.priority == priority)
+                    .cloned()
+                    .collect();
+                (priority.label().to_owned(), todos)
+            })
+            .collect(),
+        GroupBy::Tag => {
+            let tags = todos
+                .iter()
+                .flat_map(|todo| todo.details.tags.iter().cloned())
+                .collect::<BTreeSet<_>>();
+            let mut groups = tags
+                .into_iter()
+                .map(|tag| {
+                    let tagged = todos
+                        .iter()
+                        .filter(|todo| todo.details.tags.contains(&tag))
+                        .cloned()
+                        .collect();
+                    (format!("#{tag}"), tagged)
+                })
+                .collect::<Vec<_>>();
+            let untagged = todos
+                .into_iter()
+                .filter(|todo| todo.details.tags.is_empty())
+                .collect();
+            groups.push(("Untagged".to_owned(), untagged));
+            groups
+        }
+    };
+    groups
+        .into_iter()
+        .filter(|(_, todos)| !todos.is_empty())
+        .collect()
+}
+
+/// Every tag in use, sorted.
+fn all_tags(todos: &[Todo]) -> Vec<String> {
+    todos
+        .iter()
+        .flat_map(|todo| todo.details.tags.iter().cloned())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[component]
+fn TodoFilters(
+    todos: Signal<Vec<Todo>>,
+    filter: RwSignal<TodoFilter>,
+    group_by: RwSignal<GroupBy>,
+) -> impl IntoView {
+    view! {
+        <div class="todo-filters">
+            <label>
+                <input
+                    type="checkbox"
+                    prop:checked=move || filter.with(|f| f.show_done)
+                    on:change=move |ev| {
+                        let checked = event_target_checked(&ev);
+                        filter.update(|f| f.show_done = checked)
+                    }
+                />
+                " Show done"
+            </label>
+            <select
+                aria-label="Priority"
+                on:change=move |ev| {
+                    let priority = Priority::from_name(&event_target_value(&ev));
+                    filter.update(|f| f.priority = priority)
+                }
+            >
+                <option value="">"Any priority"</option>
+                {Priority::ALL
+                    .into_iter()
+                    .map(|p| view! { <option value=p.name()>{p.label()}</option> })
+                    .collect_view()}
+            </select>
+            <select
+                aria-label="Due"
+                on:change=move |ev| {
+                    let due = DueBucket::from_name(&event_target_value(&ev));
+                    filter.update(|f| f.due = due)
+                }
+            >
+                <option value="">"Any time"</option>
+                {DueBucket::ALL
+                    .into_iter()
+                    .map(|b| view! { <option value=b.name()>{b.label()}</option> })
+                    .collect_view()}
+            </select>
+            <select
+                aria-label="Tag"
+                on:change=move |ev| {
+                    let tag = Some(event_target_value(&ev)).filter(|tag| !tag.is_empty());
+                    filter.update(|f| f.tag = tag)
+                }
+            >
+                <option value="">"Any tag"</option>
+                {move || {
+                    todos
+                        .with(|todos| all_tags(todos))
+                        .into_iter()
+                        .map(|tag| {
+                            let selected = {
+                                let tag = tag.clone();
+                                move || filter.with(|f| f.tag.as_ref() == Some(&tag))
+                            };
+                            view! {
+                                <option value=tag.clone() selected=selected>
+                                    {format!("#{tag}")}
+                                </option>
+                            }
+                        })
+                        .collect_view()
+                }}
+
+            </select>
+            <label>
+                "Group by "
+                <select on:change=move |ev| {
+                    let name = event_target_value(&ev);
+                    if let Some(by) = GroupBy::ALL.into_iter().find(|by| by.name() == name) {
+                        group_by.set(by);
+                    }
+                }>
+                    {GroupBy::ALL
+                        .into_iter()
+                        .map(|by| view! { <option value=by.name()>{by.label()}</option> })
+                        .collect_view()}
+                </select>
+            </label>
+        </div>
+    }
+}
+
+/// Todos, filtered and grouped by their details.
+#[component]
+pub fn TodoList(#[prop(into)] todos: Signal<Vec<Todo>>) -> impl IntoView {
+    let filter = create_rw_signal(TodoFilter::default());
+    let group_by = create_rw_signal(GroupBy::default());
+    let groups = create_memo(move |_| {
+        let now = Local::now();
+        let shown = todos.with(|todos| {
+            filter.with(|filter| {
+                todos
+                    .iter()
+                    .filter(|todo| filter.matches(todo, now))
+                    .cloned()
+                    .collect()
+            })
+        });
+        group(shown, group_by.get(), now)
+    });
+
+    view! {
+        <TodoFilters todos filter group_by/>
+        <For
+            each=move || groups.get()
+            key=|(label, _)| label.clone()
+            children=move |(label, _)| {
+                let items = {
+                    let label = label.clone();
+                    Signal::derive(move || {
+                        groups
+                            .with(|groups| {
+                                groups
+                                    .iter()
+                                    .find(|(l, _)| *l == label)
+                                    .map(|(_, todos)| todos.clone())
+                                    .unwrap_or_default()
+                            })
+                    })
+                };
+                let heading = (!label.is_empty())
+                    .then(|| {
+                        view! {
+                            <h3 class="todo-group">
+                                {label} " (" {move || items.with(Vec::len)} ")"
+                            </h3>
+                        }
+                    });
+                view! {
+                    {heading}
+                    <ul class="todos">
+                        <For
+                            each=move || items.get()
+                            // any change to a todo re-renders its row
+                            key=|todo| todo.clone()
+                            children=|todo| view! { <TodoItem todo/> }
+                        />
+
+                    </ul>
+                }
+            }
+        />
+    }
+}
+
+#[component]
+fn TodoItem(todo: Todo) -> impl IntoView {
+    // queued like additions, so a change made offline is kept until it applies
+    let outbox = use_outbox();
+    let history = use_history();
+    let toasts = use_toasts();
+    let modals = use_modals();
+    let can_write = use_can(Capability::TodoWrite);
+    let can_delete = use_can(Capability::TodoDelete);
+    let Todo {
+        id,
+        text,
+        done,
+        details,
+        pending,
+    } = todo.clone();
+
+    let toggle_done = move |_| {
+        // the id the next occurrence gets, if the todo recurs
+        let next_id = Uuid::new_v4();
+        let complete = Mutation::CompleteTodo { id, next_id };
+        let reopen = Mutation::ReopenTodo { id, next_id };
+        let (change, inverse, message) = if done {
+            (reopen, complete, "Todo reopened")
+        } else {
+            (complete, reopen, "Todo completed")
+        };
+        queue_undoable(outbox, history, toasts, ToastLevel::Success, message, change, inverse);
+    };
+    let edit = {
+        let todo = todo.clone();
+        move |_| {
+            let todo = todo.clone();
+            modals.open(move |handle| view! { <TodoEditor todo=todo.clone() handle/> }, || {});
+        }
+    };
+    let delete = {
+        let (text, details) = (text.clone(), details.clone());
+        move |_| {
+            queue_undoable(
+                outbox,
+                history,
+                toasts,
+                ToastLevel::Info,
+                "Todo deleted",
+                Mutation::DeleteTodo { id },
+                Mutation::AddTodo {
+                    id,
+                    text: text.clone(),
+                    details: details.clone(),
+                },
+            )
+        }
+    };
+    let TodoDetails {
+        due,
+        priority,
+        tags,
+        recurrence,
+        remind_before,
+        ..
+    } = details;
+
+    view! {
+        <li class:pending=pending class:done=done class=format!("priority-{}", priority.name())>
+            <input
+                type="checkbox"
+                aria-label="Done"
+                checked=done
+                disabled=move || !can_write.get()
+                on:change=toggle_done
+            />
+            " "
+            {text}
+            {due.map(|due| view! { <span class="todo-due">{format_due(due)}</span> })}
+            {recurrence.map(|r| view! { <span class="todo-recurrence">"↻ " {r.label()}</span> })}
+            {remind_before
+                .filter(|_| due.is_some())
+                .map(|_| view! { <span class="todo-reminder" title="Reminder set">"🔔"</span> })}
+            {tags
+                .into_iter()
+                .map(|tag| view! { <span class="todo-tag">"#" {tag}</span> })
+                .collect_view()}
+            " "
+            <button disabled=move || !can_write.get() on:click=edit>
+                "Edit"
+            </button>
+            <button disabled=move || !can_delete.get() on:click=delete>
+                "Delete"
+            </button>
+        </li>
+    }
+}
+
+/// Edits a todo's text and details in a modal.
+#[component]
+fn TodoEditor(todo: Todo, handle: ModalHandle) -> impl IntoView {
+    let outbox = use_outbox();
+    let history = use_history();
+    let toasts = use_toasts();
+    let details = &todo.details;
+    let text = create_rw_signal(todo.text.clone());
+    let due = create_rw_signal(to_input(details.due));
+    let priority = create_rw_signal(details.priority);
+    let tags = create_rw_signal(details.tags.join(", "));
+    let notes = create_rw_signal(details.notes.clone());
+    let (repeat, rrule) = match &details.recurrence {
+        None => ("none", String::new()),
+        Some(Recurrence::Daily) => ("daily", String::new()),
+        Some(Recurrence::Weekly) => ("weekly", String::new()),
+        Some(Recurrence::Rule { rrule }) => ("rule", rrule.clone()),
+    };
+    let repeat = create_rw_signal(repeat);
+    let remind_before = create_rw_signal(details.remind_before);
+    let rrule = create_rw_signal(rrule);
+
+    let save = move |ev: ev::SubmitEvent| {
+        ev.prevent_default();
+        let details = TodoDetails {
+            due: from_input(&due.get_untracked()),
+            priority: priority.get_untracked(),
+            tags: tags
+                .get_untracked()
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches('#').to_owned())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            notes: notes.get_untracked(),
+            recurrence: match repeat.get_untracked() {
+                "daily" => Some(Recurrence::Daily),
+                "weekly" => Some(Recurrence::Weekly),
+                "rule" => Some(Recurrence::Rule {
+                    rrule: rrule.get_untracked(),
+                }),
+                _ => None,
+            },
+            remind_before: remind_before.get_untracked(),
+        };
+        let change = Mutation::UpdateTodo {
+            id: todo.id,
+            text: text.get_untracked(),
+            details,
+        };
+        let inverse = Mutation::UpdateTodo {
+            id: todo.id,
+            text: todo.text.clone(),
+            details: todo.details.clone(),
+        };
+        let level = ToastLevel::Success;
+        queue_undoable(outbox, history, toasts, level, "Todo updated", change, inverse);
+        handle.close();
+    };
+
+    view! {
+        <form class="todo-editor" on:submit=save>
+            <h2>"Edit todo"</h2>
+            <label>
+                "Text"
+                <input
+                    type="text"
+                    prop:value=text
+                    on:input=move |ev| text.set(event_target_value(&ev))
+                />
+            </label>
+            <label>
+                "Due"
+                <input
+                    type="datetime-local"
+                    prop:value=due
+                    on:input=move |ev| due.set(event_target_value(&ev))
+                />
+            </label>
+            <label>
+                "Remind"
+                <select on:change=move |ev| {
+                    let index = event_target_value(&ev).parse::<usize>().ok();
+                    if let Some((minutes, _)) = index.and_then(|i| REMINDERS.get(i)) {
+                        remind_before.set(*minutes);
+                    }
+                }>
+                    {REMINDERS
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, (minutes, label))| {
+                            view! {
+                                <option
+                                    value=index.to_string()
+                                    selected=move || remind_before.get() == minutes
+                                >
+                                    {label}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+            </label>
+            <label>
+                "Priority"
+                <select on:change=move |ev| {
+                    if let Some(p) = Priority::from_name(&event_target_value(&ev)) {
+                        priority.set(p);
+                    }
+                }>
+                    {Priority::ALL
+                        .into_iter()
+                        .map(|p| {
+                            view! {
+                                <option value=p.name() selected=move || priority.get() == p>
+                                    {p.label()}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+            </label>
+            <label>
+                "Tags"
+                <input
+                    type="text"
+                    placeholder="work, errands"
+                    prop:value=tags
+                    on:input=move |ev| tags.set(event_target_value(&ev))
+                />
+            </label>
+            <label>
+                "Notes"
+                <textarea prop:value=notes on:input=move |ev| notes.set(event_target_value(&ev))>
+                </textarea>
+            </label>
+            <label>
+                "Repeat"
+                <select on:change=move |ev| {
+                    let value = event_target_value(&ev);
+                    if let Some((value, _)) = REPEATS.into_iter().find(|(v, _)| *v == value) {
+                        repeat.set(value);
+                    }
+                }>
+                    {REPEATS
+                        .into_iter()
+                        .map(|(value, label)| {
+                            view! {
+                                <option value=value selected=move || repeat.get() == value>
+                                    {label}
+                                </option>
+                            }
+                        })
+                        .collect_view()}
+                </select>
+            </label>
+            <Show when=move || repeat.get() == "rule">
+                <label>
+                    "Rule"
+                    <input
+                        type="text"
+                        placeholder="FREQ=MONTHLY;BYMONTHDAY=1"
+                        prop:value=rrule
+                        on:input=move |ev| rrule.set(event_target_value(&ev))
+                    />
+                </label>
+            </Show>
+            <div class="modal-buttons">
+                <button type="button" on:click=move |_| handle.close()>
+                    "Cancel"
+                </button>
+                <button type="submit">"Save"</button>
+            </div>
+        </form>
+    }
+}
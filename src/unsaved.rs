@@ -0,0 +1,190 @@
+use leptos::*;
+use leptos_router::{use_location, use_navigate};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+use web_sys::{Element, MouseEvent};
+
+use crate::ipc::{self, NoArgs};
+use crate::modal::{use_modals, Modals};
+
+/// Components with edits that would be lost by closing the window or
+/// navigating away.
+#[derive(Clone, Copy)]
+pub struct Unsaved {
+    sources: RwSignal<Vec<(u64, String, Signal<bool>)>>,
+    next_key: StoredValue<u64>,
+    modals: Modals,
+}
+
+pub fn provide_unsaved() {
+    provide_context(Unsaved {
+        sources: create_rw_signal(Vec::new()),
+        next_key: store_value(0),
+        modals: use_modals(),
+    });
+}
+
+pub fn use_unsaved() -> Unsaved {
+    expect_context::<Unsaved>()
+}
+
+/// Counts `dirty` towards unsaved changes until the current reactive scope
+/// is disposed. `label` names what would be lost, like `Text area`.
+pub fn register_unsaved(label: impl Into<String>, dirty: impl Into<Signal<bool>>) {
+    let unsaved = use_unsaved();
+    let key = unsaved.register(label.into(), dirty.into());
+    on_cleanup(move || unsaved.unregister(key));
+}
+
+#[derive(Serialize)]
+struct SetUnsavedArgs {
+    dirty: bool,
+}
+
+impl Unsaved {
+    /// Prefer [`register_unsaved`], which also unregisters.
+    pub fn register(&self, label: String, dirty: Signal<bool>) -> u64 {
+        let key = self.next_key.get_value();
+        self.next_key.set_value(key + 1);
+        self.sources
+            .update(|sources| sources.push((key, label, dirty)));
+        key
+    }
+
+    pub fn unregister(&self, key: u64) {
+        self.sources
+            .try_update(|sources| sources.retain(|(k, _, _)| *k != key));
+    }
+
+    pub fn is_dirty(&self) -> Signal<bool> {
+        let sources = self.sources;
+        Signal::derive(move || sources.with(|sources| sources.iter().any(|(_, _, d)| d.get())))
+    }
+
+    fn dirty_labels_untracked(&self) -> Vec<String> {
+        self.sources.with_untracked(|sources| {
+            sources
+                .iter()
+                .filter(|(_, _, dirty)| dirty.get_untracked())
+                .map(|(_, label, _)| label.clone())
+                .collect()
+        })
+    }
+
+    /// Whether it is fine to lose the unsaved changes: right away if there
+    /// are none, otherwise once the user has answered.
+    pub async fn confirm_discard(&self) -> bool {
+        let labels = self.dirty_labels_untracked();
+        if labels.is_empty() {
+            return true;
+        }
+        let message = format!("Discard unsaved changes to {}?", labels.join(", "));
+        self.modals.confirm(message).await
+    }
+
+    /// Runs `then` unless the user would rather keep their unsaved changes.
+    pub fn guard(&self, then: impl FnOnce() + 'static) {
+        let this = *self;
+        spawn_local(async move {
+            if this.confirm_discard().await {
+                then();
+            }
+        });
+    }
+}
+
+/// The path a click on an in-app link navigates to.
+fn internal_link(ev: &MouseEvent) -> Option<String> {
+    let target = ev.target()?.dyn_into::<Element>().ok()?;
+    let anchor = target.closest("a[href]").ok()??;
+    if anchor.has_attribute("target") || anchor.has_attribute("download") {
+        return None;
+    }
+    let href = anchor.get_attribute("href")?;
+    (href.starts_with('/') && !href.starts_with("//")).then_some(href)
+}
+
+/// Shows unsaved changes in the window title, and asks before they are lost
+/// by closing the window, quitting or following an in-app link. Rendered
+/// inside the router.
+///
+/// Back and forward aren't guarded: the router has already changed the URL
+/// by the time it sees the `popstate`, and the history can't be vetoed. Nor
+/// are `<Form method="GET">` submissions, which navigate from the router's
+/// own submit handler; forms on pages with unsaved changes should check
+/// [`Unsaved::confirm_discard`] themselves.
+#[component]
+pub fn UnsavedGuard() -> impl IntoView {
+    let unsaved = use_unsaved();
+    let dirty = create_memo({
+        let is_dirty = unsaved.is_dirty();
+        move |_| is_dirty.get()
+    });
+    create_effect(move |_| {
+        let args = SetUnsavedArgs { dirty: dirty.get() };
+        spawn_local(async move {
+            if let Err(err) = ipc::call::<_, ()>("set_unsaved", &args).await {
+                log::warn!("failed to mark unsaved changes: {err}");
+            }
+        });
+    });
+
+    // the backend vetoes the close and leaves it to us
+    ipc::listen("window://close-requested", move |()| {
+        unsaved.guard(|| {
+            spawn_local(async {
+                if let Err(err) = ipc::call::<_, ()>("close_window", &NoArgs {}).await {
+                    log::warn!("failed to close the window: {err}");
+                }
+            });
+        });
+    });
+    // likewise for quitting, from the menu or the tray
+    ipc::listen("window://quit-requested", move |()| {
+        unsaved.guard(|| {
+            spawn_local(async {
+                if let Err(err) = ipc::call::<_, ()>("quit_app", &NoArgs {}).await {
+                    log::warn!("failed to quit: {err}");
+                }
+            });
+        });
+    });
+
+    // The router follows links from its own click listener on the window, and
+    // skips clicks whose default was prevented; listening in the capture
+    // phase gets there first.
+    let navigate = use_navigate();
+    let location = use_location();
+    let on_click = Closure::<dyn Fn(MouseEvent)>::new(move |ev: MouseEvent| {
+        if ev.default_prevented()
+            || ev.button() != 0
+            || ev.ctrl_key()
+            || ev.meta_key()
+            || ev.shift_key()
+            || ev.alt_key()
+        {
+            return;
+        }
+        let Some(path) = internal_link(&ev) else {
+            return;
+        };
+        if path == location.pathname.get_untracked() || !dirty.get_untracked() {
+            return;
+        }
+        ev.prevent_default();
+        let navigate = navigate.clone();
+        unsaved.guard(move || navigate(&path, Default::default()));
+    });
+    _ = window().add_event_listener_with_callback_and_bool(
+        "click",
+        on_click.as_ref().unchecked_ref(),
+        true,
+    );
+    on_cleanup(move || {
+        _ = window().remove_event_listener_with_callback_and_bool(
+            "click",
+            on_click.as_ref().unchecked_ref(),
+            true,
+        );
+    });
+}
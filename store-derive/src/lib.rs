@@ -0,0 +1,492 @@
+//! `#[derive(Store)]`, which turns a plain struct into a reactive store with
+//! a signal per field, so a view that reads one field only re-renders when
+//! that field changes.
+//!
+//! For `struct Entry { key: String, value: i32 }` it generates `EntryStore`,
+//! a `Copy` handle with:
+//!
+//! - `EntryStore::new(entry)`, also `EntryStore::from(entry)`;
+//! - an accessor per field, here `key()` and `value()`, returning its signal;
+//! - `get()` and `get_untracked()`, the plain `Entry` back, for saving;
+//! - `set(entry)`, which only notifies the fields that changed;
+//! - `SignalDispose`, which disposes every signal in it.
+//!
+//! Fields can be marked with:
+//!
+//! - `#[store(nested)]` for a field whose type derives `Store` too; the
+//!   accessor returns its store rather than a signal;
+//! - `#[store(key = field)]` for a `Vec` of a type deriving `Store`, keyed by
+//!   one of its fields; the accessor returns a signal of the items' stores,
+//!   and `set` keeps the store of an item whose key is still there, so a
+//!   keyed `<For>` over them only renders new items. The stores of items
+//!   that are gone are disposed.
+//!
+//! Field types must be `PartialEq`, so `set` can tell what changed. The
+//! generated code refers to `leptos`, which the deriving crate must depend
+//! on.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Field, Fields, GenericArgument, Ident,
+    PathArguments, Result, Type, TypePath,
+};
+
+/// Methods every store has, which fields can't share names with.
+const RESERVED: &[&str] = &["new", "get", "get_untracked", "set"];
+
+#[proc_macro_derive(Store, attributes(store))]
+pub fn derive_store(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field is kept in the store.
+enum Kind {
+    /// In a signal of its own.
+    Signal,
+    /// In the store of its type.
+    Nested { store: TypePath },
+    /// In a signal of its items' stores, matched up by `key` on `set`.
+    Keyed { store: TypePath, key: Ident },
+}
+
+struct StoreField {
+    name: Ident,
+    ty: Type,
+    kind: Kind,
+}
+
+impl StoreField {
+    fn parse(field: &Field) -> Result<Self> {
+        let name = field
+            .ident
+            .clone()
+            .ok_or_else(|| Error::new(field.span(), "Store needs named fields"))?;
+        if RESERVED.iter().any(|reserved| name == reserved) {
+            return Err(Error::new(
+                name.span(),
+                format!("a field named `{name}` would clash with `Store::{name}`"),
+            ));
+        }
+        let mut nested = false;
+        let mut key = None;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("store")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("nested") {
+                    nested = true;
+                    Ok(())
+                } else if meta.path.is_ident("key") {
+                    key = Some(meta.value()?.parse::<Ident>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `nested` or `key = field`"))
+                }
+            })?;
+        }
+        let kind = match (nested, key) {
+            (false, None) => Kind::Signal,
+            (true, None) => Kind::Nested {
+                store: store_of(&field.ty)?,
+            },
+            (false, Some(key)) => Kind::Keyed {
+                store: store_of(vec_item(&field.ty)?)?,
+                key,
+            },
+            (true, Some(key)) => {
+                return Err(Error::new(key.span(), "`nested` and `key` can't be combined"));
+            }
+        };
+        Ok(Self {
+            name,
+            ty: field.ty.clone(),
+            kind,
+        })
+    }
+
+    /// The type the store keeps the field as.
+    fn store_type(&self) -> TokenStream2 {
+        let ty = &self.ty;
+        match &self.kind {
+            Kind::Signal => quote!(::leptos::RwSignal<#ty>),
+            Kind::Nested { store } => quote!(#store),
+            Kind::Keyed { store, .. } => quote!(::leptos::RwSignal<::std::vec::Vec<#store>>),
+        }
+    }
+
+    /// Makes the field's store from `plain.<field>`.
+    fn create(&self) -> TokenStream2 {
+        let name = &self.name;
+        match &self.kind {
+            Kind::Signal => quote!(::leptos::create_rw_signal(plain.#name)),
+            Kind::Nested { store } => quote!(#store::new(plain.#name)),
+            Kind::Keyed { store, .. } => quote! {
+                ::leptos::create_rw_signal(
+                    plain.#name.into_iter().map(#store::new).collect(),
+                )
+            },
+        }
+    }
+
+    /// Reads the plain field back, tracked unless `untracked`.
+    fn read(&self, untracked: bool) -> TokenStream2 {
+        let name = &self.name;
+        let (get, with, item_get) = if untracked {
+            (
+                quote!(::leptos::SignalGetUntracked::get_untracked),
+                quote!(::leptos::SignalWithUntracked::with_untracked),
+                quote!(get_untracked),
+            )
+        } else {
+            (
+                quote!(::leptos::SignalGet::get),
+                quote!(::leptos::SignalWith::with),
+                quote!(get),
+            )
+        };
+        match &self.kind {
+            Kind::Signal => quote!(#get(&self.#name)),
+            Kind::Nested { .. } => quote!(self.#name.#item_get()),
+            Kind::Keyed { store, .. } => quote! {
+                #with(&self.#name, |items| items.iter().map(#store::#item_get).collect())
+            },
+        }
+    }
+
+    /// Updates the field's store from `plain.<field>`, notifying only if it
+    /// changed.
+    fn update(&self) -> TokenStream2 {
+        let name = &self.name;
+        match &self.kind {
+            Kind::Signal => quote! {
+                if ::leptos::SignalWithUntracked::with_untracked(
+                    &self.#name,
+                    |current| *current != plain.#name,
+                ) {
+                    ::leptos::SignalSet::set(&self.#name, plain.#name);
+                }
+            },
+            Kind::Nested { .. } => quote!(self.#name.set(plain.#name);),
+            Kind::Keyed { store, key } => quote! {
+                let mut stale = ::leptos::SignalGetUntracked::get_untracked(&self.#name);
+                let next: ::std::vec::Vec<#store> = plain
+                    .#name
+                    .into_iter()
+                    .map(|item| {
+                        let existing = stale.iter().position(|store| {
+                            ::leptos::SignalWithUntracked::with_untracked(
+                                &store.#key(),
+                                |key| *key == item.#key,
+                            )
+                        });
+                        match existing {
+                            Some(index) => {
+                                let store = stale.remove(index);
+                                store.set(item);
+                                store
+                            }
+                            None => #store::new(item),
+                        }
+                    })
+                    .collect();
+                if ::leptos::SignalWithUntracked::with_untracked(
+                    &self.#name,
+                    |current| *current != next,
+                ) {
+                    ::leptos::SignalSet::set(&self.#name, next);
+                }
+                // only once no longer listed, so nothing reads them anymore
+                for store in stale {
+                    ::leptos::SignalDispose::dispose(store);
+                }
+            },
+        }
+    }
+
+    /// Disposes the field's signals.
+    fn dispose(&self) -> TokenStream2 {
+        let name = &self.name;
+        match &self.kind {
+            Kind::Signal | Kind::Nested { .. } => {
+                quote!(::leptos::SignalDispose::dispose(self.#name);)
+            }
+            Kind::Keyed { .. } => quote! {
+                for store in ::leptos::SignalGetUntracked::get_untracked(&self.#name) {
+                    ::leptos::SignalDispose::dispose(store);
+                }
+                ::leptos::SignalDispose::dispose(self.#name);
+            },
+        }
+    }
+}
+
+/// The store `#[derive(Store)]` generates for `ty`: the same path with
+/// `Store` added to the last segment.
+fn store_of(ty: &Type) -> Result<TypePath> {
+    let Type::Path(path) = ty else {
+        return Err(Error::new(ty.span(), "expected a struct deriving Store"));
+    };
+    let mut store = path.clone();
+    let last = store
+        .path
+        .segments
+        .last_mut()
+        .ok_or_else(|| Error::new(ty.span(), "expected a struct deriving Store"))?;
+    last.ident = format_ident!("{}Store", last.ident);
+    Ok(store)
+}
+
+/// `T` for `Vec<T>`.
+fn vec_item(ty: &Type) -> Result<&Type> {
+    let error = || Error::new(ty.span(), "`key` needs a `Vec` of a struct deriving Store");
+    let Type::Path(path) = ty else {
+        return Err(error());
+    };
+    let last = path.path.segments.last().ok_or_else(error)?;
+    if last.ident != "Vec" {
+        return Err(error());
+    }
+    let PathArguments::AngleBracketed(args) = &last.arguments else {
+        return Err(error());
+    };
+    match args.args.first() {
+        Some(GenericArgument::Type(item)) if args.args.len() == 1 => Ok(item),
+        _ => Err(error()),
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "Store can't be derived for generic structs",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(input.ident.span(), "Store can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(data.fields.span(), "Store needs named fields"));
+    };
+    let fields = fields
+        .named
+        .iter()
+        .map(StoreField::parse)
+        .collect::<Result<Vec<_>>>()?;
+
+    let vis = &input.vis;
+    let plain = &input.ident;
+    let store = format_ident!("{plain}Store");
+    let doc = format!("[`{plain}`] with a signal per field, from `#[derive(Store)]`.");
+    let names = fields.iter().map(|field| &field.name).collect::<Vec<_>>();
+    let store_types = fields.iter().map(StoreField::store_type).collect::<Vec<_>>();
+    let creates = fields.iter().map(StoreField::create);
+    let reads = fields.iter().map(|field| field.read(false));
+    let untracked_reads = fields.iter().map(|field| field.read(true));
+    let updates = fields.iter().map(StoreField::update);
+    let disposes = fields.iter().map(StoreField::dispose);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #vis struct #store {
+            #(#names: #store_types,)*
+        }
+
+        impl #store {
+            #vis fn new(plain: #plain) -> Self {
+                Self {
+                    #(#names: #creates,)*
+                }
+            }
+
+            #(
+                #vis fn #names(&self) -> #store_types {
+                    self.#names
+                }
+            )*
+
+            /// The plain struct, tracking every field.
+            #vis fn get(&self) -> #plain {
+                #plain {
+                    #(#names: #reads,)*
+                }
+            }
+
+            #vis fn get_untracked(&self) -> #plain {
+                #plain {
+                    #(#names: #untracked_reads,)*
+                }
+            }
+
+            /// Updates the fields from `plain`, notifying only those that
+            /// changed.
+            #vis fn set(&self, plain: #plain) {
+                #(#updates)*
+            }
+        }
+
+        /// Disposes every signal in the store, including those of nested and
+        /// keyed stores.
+        impl ::leptos::SignalDispose for #store {
+            fn dispose(self) {
+                #(#disposes)*
+            }
+        }
+
+        impl ::std::convert::From<#plain> for #store {
+            fn from(plain: #plain) -> Self {
+                Self::new(plain)
+            }
+        }
+
+        impl ::std::convert::From<#store> for #plain {
+            fn from(store: #store) -> Self {
+                store.get_untracked()
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, File, Item};
+
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        match expand(input) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    /// The type of `field` in the generated store.
+    fn store_field_type(input: DeriveInput, field: &str) -> String {
+        let store = format_ident!("{}Store", input.ident);
+        let file: File = syn::parse2(expand(input).unwrap()).unwrap();
+        let fields = file
+            .items
+            .iter()
+            .find_map(|item| match item {
+                Item::Struct(item) if item.ident == store => Some(&item.fields),
+                _ => None,
+            })
+            .expect("no store struct generated");
+        let ty = &fields
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|ident| ident == field))
+            .expect("no such field in the store")
+            .ty;
+        quote!(#ty).to_string()
+    }
+
+    #[test]
+    fn plain_field_is_a_signal() {
+        let input = parse_quote! {
+            struct Entry {
+                value: i32,
+            }
+        };
+        assert_eq!(
+            store_field_type(input, "value"),
+            quote!(::leptos::RwSignal<i32>).to_string()
+        );
+    }
+
+    #[test]
+    fn nested_field_is_its_store() {
+        let input = parse_quote! {
+            struct Board {
+                #[store(nested)]
+                origin: geometry::Position,
+            }
+        };
+        assert_eq!(
+            store_field_type(input, "origin"),
+            quote!(geometry::PositionStore).to_string()
+        );
+    }
+
+    #[test]
+    fn keyed_field_is_a_signal_of_item_stores() {
+        let input = parse_quote! {
+            struct Board {
+                #[store(key = id)]
+                items: Vec<Item>,
+            }
+        };
+        assert_eq!(
+            store_field_type(input, "items"),
+            quote!(::leptos::RwSignal<::std::vec::Vec<ItemStore>>).to_string()
+        );
+    }
+
+    #[test]
+    fn keyed_set_disposes_stale_stores() {
+        let input: DeriveInput = parse_quote! {
+            struct Board {
+                #[store(key = id)]
+                items: Vec<Item>,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        let stale = quote!(for store in stale {
+            ::leptos::SignalDispose::dispose(store);
+        });
+        assert!(expanded.contains(&stale.to_string()));
+    }
+
+    #[test]
+    fn rejects_reserved_field_names() {
+        for reserved in RESERVED {
+            let name = format_ident!("{reserved}");
+            let input = parse_quote! {
+                struct Entry {
+                    #name: i32,
+                }
+            };
+            assert_eq!(
+                error(input),
+                format!("a field named `{reserved}` would clash with `Store::{reserved}`")
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_nested_and_key_together() {
+        let input = parse_quote! {
+            struct Board {
+                #[store(nested, key = id)]
+                items: Vec<Item>,
+            }
+        };
+        assert_eq!(error(input), "`nested` and `key` can't be combined");
+    }
+
+    #[test]
+    fn rejects_key_on_anything_but_a_vec() {
+        let input = parse_quote! {
+            struct Board {
+                #[store(key = id)]
+                items: HashSet<Item>,
+            }
+        };
+        assert_eq!(error(input), "`key` needs a `Vec` of a struct deriving Store");
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let input = parse_quote! {
+            struct Entry {
+                #[store(flatten)]
+                value: i32,
+            }
+        };
+        assert_eq!(error(input), "expected `nested` or `key = field`");
+    }
+}
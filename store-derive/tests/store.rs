@@ -0,0 +1,99 @@
+use leptos::*;
+use store_derive::Store;
+
+#[derive(Debug, Clone, PartialEq, Store)]
+struct Item {
+    id: u32,
+    label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Store)]
+struct Position {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Store)]
+struct Board {
+    title: String,
+    #[store(nested)]
+    origin: Position,
+    #[store(key = id)]
+    items: Vec<Item>,
+}
+
+fn item(id: u32, label: &str) -> Item {
+    Item {
+        id,
+        label: label.to_owned(),
+    }
+}
+
+fn board(items: Vec<Item>) -> Board {
+    Board {
+        title: "Board".to_owned(),
+        origin: Position { x: 1, y: 2 },
+        items,
+    }
+}
+
+#[test]
+fn get_returns_what_was_set() {
+    let runtime = create_runtime();
+    let store = BoardStore::new(board(vec![item(1, "one")]));
+    assert_eq!(store.get_untracked(), board(vec![item(1, "one")]));
+
+    let mut changed = board(vec![item(1, "uno"), item(2, "dos")]);
+    changed.title = "Renamed".to_owned();
+    changed.origin.y = 5;
+    store.set(changed.clone());
+    assert_eq!(store.get(), changed);
+    assert_eq!(store.title().get_untracked(), "Renamed");
+    assert_eq!(store.origin().x().get_untracked(), 1);
+    assert_eq!(store.origin().y().get_untracked(), 5);
+    runtime.dispose();
+}
+
+#[test]
+fn keyed_set_keeps_the_stores_of_remaining_items() {
+    let runtime = create_runtime();
+    let store = BoardStore::new(board(vec![item(1, "one"), item(2, "two"), item(3, "three")]));
+    let before = store.items().get_untracked();
+
+    store.set(board(vec![item(3, "three"), item(1, "uno"), item(4, "four")]));
+    let after = store.items().get_untracked();
+    assert_eq!(after[0], before[2]);
+    assert_eq!(after[1], before[0]);
+    assert!(!before.contains(&after[2]));
+    assert_eq!(after[1].label().get_untracked(), "uno");
+    assert_eq!(after[2].id().get_untracked(), 4);
+    runtime.dispose();
+}
+
+#[test]
+fn keyed_set_disposes_the_stores_of_removed_items() {
+    let runtime = create_runtime();
+    let store = BoardStore::new(board(vec![item(1, "one"), item(2, "two")]));
+    let removed = store.items().get_untracked()[1];
+
+    store.set(board(vec![item(1, "one")]));
+    assert_eq!(removed.label().try_get_untracked(), None);
+    assert_eq!(
+        store.items().get_untracked()[0].label().try_get_untracked(),
+        Some("one".to_owned())
+    );
+    runtime.dispose();
+}
+
+#[test]
+fn dispose_disposes_nested_and_keyed_stores() {
+    let runtime = create_runtime();
+    let store = BoardStore::new(board(vec![item(1, "one")]));
+    let first = store.items().get_untracked()[0];
+
+    store.dispose();
+    assert_eq!(store.title().try_get_untracked(), None);
+    assert_eq!(store.origin().x().try_get_untracked(), None);
+    assert_eq!(first.label().try_get_untracked(), None);
+    runtime.dispose();
+}